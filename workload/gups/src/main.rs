@@ -1,14 +1,23 @@
 use std::{
-    cell::RefCell,
-    marker, mem, ops, process, slice,
-    sync::{self, Arc},
+    cell::{Cell, RefCell},
+    io::{self, Read, Write},
+    marker, mem, ops, path, process, slice,
+    sync::{
+        self,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
     time,
 };
 
 use async_std::{prelude::*, stream};
 use futures::{channel::mpsc, join, pin_mut, select, FutureExt, StreamExt};
 use mix_distribution::Mix;
-use rand::distributions::{Distribution, Uniform};
+use rand::{
+    distributions::{Distribution, Uniform},
+    Rng,
+};
+use rand_distr::{Exp, Normal, Pareto};
 use rayon::prelude::*;
 use zipf::ZipfDistribution;
 
@@ -17,8 +26,8 @@ use structopt::StructOpt;
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
 /// GUPS hotset version with `weight` times as more updates going to the hot region than to the rest.
-#[derive(StructOpt, Debug, Clone, Copy)]
-#[structopt(name = "Gups", about = "Gibi updates per second.")]
+#[derive(StructOpt, Debug, Clone, serde::Serialize)]
+#[structopt(name = "Gups", about = "Giga updates per second.")]
 struct Args {
     /// Number of worker threads
     #[structopt(short, long)]
@@ -26,34 +35,571 @@ struct Args {
     /// Number of updates in total
     #[structopt(short, long)]
     update: usize,
-    /// Length of the entire memory region
-    #[structopt(short, long)]
+    /// Length of the entire memory region, such as `64G` or `64GB` in decimal
+    #[structopt(short, long, parse(try_from_str = parse_size))]
     len: usize,
-    /// Granularity of each update
+    /// Granularity of each update, or weighted sizes such as `8:90,64:9,4K:1`
     #[structopt(short, long)]
-    granularity: usize,
+    granularity: Granularity,
     /// Show the gups every given interval in ms
     #[structopt(short, long)]
     report: Option<u64>,
+    /// Unit of the updates per second shown: `gups` for 1e9 as in the literature, `mups` for 1e6
+    /// or `ops` for single updates
+    #[structopt(long, default_value = "gups")]
+    unit: Unit,
+    /// Sample the memory usage of `/proc/self/smaps_rollup` in each report as well as at the end of
+    /// each iteration
+    #[structopt(long)]
+    smaps: bool,
+    /// Show the spread of the updates per second between the threads in each report and those of
+    /// each thread at the end of each iteration, along with its CPU time and context switches
+    #[structopt(long)]
+    per_thread: bool,
+    /// Leave out the periodic reports of each iteration, logging only its final summary along with
+    /// the mean, min and max of the instantaneous gups of its reports
+    #[structopt(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Show the updates per second of each thread in each report, implying `--per-thread`
+    #[structopt(short, long)]
+    verbose: bool,
+    /// Serve the metrics of the run for Prometheus at `http://<addr:port>/metrics` from a thread of
+    /// its own, implying `--per-thread` for the updates of each thread
+    #[structopt(long)]
+    prometheus: Option<String>,
+    /// Show the changes of the page migration counters of `/proc/vmstat` in each report, which are
+    /// those of the whole system rather than of this process
+    #[structopt(long)]
+    vmstat: bool,
+    /// Further counters of `/proc/vmstat` shown in each report, implying `--vmstat`, such as
+    /// `pgdemote_khugepaged,thp_migration_success`
+    #[structopt(long, require_delimiter = true)]
+    vmstat_fields: Vec<String>,
     /// Show the portion of memory pages mapped to the DRAM every given interval in ms
     #[structopt(short, long)]
     dram_ratio: Option<u64>,
+    /// Size of the chunks of the memory region whose DRAM portion is reported, a multiple of the
+    /// page size such as `2M`
+    #[structopt(long, default_value = "1G", parse(try_from_str = parse_size))]
+    dram_chunk: usize,
+    /// Write the residency of each page to the given directory at each report of the DRAM portion,
+    /// see `gups heatmap-render`
+    #[structopt(long, requires = "dram-ratio", parse(from_os_str))]
+    residency_dump: Option<path::PathBuf>,
+    /// Show the distinct pages written every given interval in ms by their soft-dirty bits, cleared
+    /// for the whole process at the start of each interval, at the same interval as `--dram-ratio`
+    #[structopt(long)]
+    soft_dirty: Option<u64>,
+    /// Show the portion of the present pages accessed every given interval in ms by the page idle
+    /// bitmap, marked idle at the start of each interval, at the same interval as `--dram-ratio`
+    #[structopt(long)]
+    idle_track: Option<u64>,
+    /// Show the pages per NUMA node of the memory region told by `/proc/self/numa_maps`, and their
+    /// changes, at each report of the DRAM portion
+    #[structopt(long, requires = "dram-ratio")]
+    numa_maps: bool,
+    /// Show the portion of the memory region backed by transparent huge pages at each report of the
+    /// DRAM portion, per chunk by the flags of `/proc/kpageflags` or for the whole region by the
+    /// `AnonHugePages` of `/proc/self/smaps` if that cannot be read
+    #[structopt(long, requires = "dram-ratio")]
+    thp_ratio: bool,
+    /// Show the portion of the pages of each chunk on each of the given tiers at each report of the
+    /// DRAM portion, along with those present elsewhere and those not present, by
+    /// `<name>=node<n>[+node<n>...],...` such as `dram=node0,cxl=node2`, the frames of a tier
+    /// being those of the memory blocks of its nodes
+    #[structopt(long, requires = "dram-ratio")]
+    tiers: Option<Tiers>,
+    /// Count the updates per bucket of the elements and compare them at the end of each iteration
+    /// with the distribution expected of the random, zipf and hotset workloads by their total
+    /// variation distance
+    #[structopt(long)]
+    check_dist: bool,
+    /// Number of equal buckets of the elements counted by `--check-dist`
+    #[structopt(long, default_value = "1024")]
+    check_dist_buckets: usize,
+    /// Largest total variation distance `--check-dist` passes
+    #[structopt(long, default_value = "0.01")]
+    check_dist_threshold: f64,
+    /// Append the updates per chunk of each iteration and their cumulative portion, both in the
+    /// order of the chunks and from the hottest chunk down, to the given CSV file
+    #[structopt(long, parse(from_os_str))]
+    cdf_out: Option<path::PathBuf>,
+    /// Size of the chunks of the memory region of `--cdf-out`
+    #[structopt(long, default_value = "1G", parse(try_from_str = parse_size))]
+    cdf_chunk: usize,
+    /// Percentage of updates that only read the element instead of incrementing it
+    #[structopt(long, default_value = "0")]
+    read_ratio: usize,
+    /// Operation performed by each update: `add` increments the element, `xor` flips it by its
+    /// index as HPCC does, `store` overwrites it with a constant, `load` only reads it and `copy`
+    /// overwrites `--copy-size` bytes starting at it from a per-thread source buffer
+    #[structopt(long, default_value = "add")]
+    op: Op,
+    /// Bytes copied by each write of the `copy` operation, cut short at the end of the region
+    #[structopt(long, default_value = "64", parse(try_from_str = parse_size))]
+    copy_size: usize,
+    /// Number of consecutive elements updated from each sampled index, wrapping at the region end
+    #[structopt(long, default_value = "1")]
+    run_length: usize,
+    /// Draw keys in `[0, keyspace)` from the workload and hash them onto the elements with `--seed`,
+    /// scattering the hot keys like a hash table
+    #[structopt(long)]
+    keyspace: Option<usize>,
+    /// Issue the updates at the given total rate per second with exponentially distributed gaps
+    /// instead of as fast as possible
+    #[structopt(long)]
+    arrival_rate: Option<f64>,
+    /// Probability of each thread repeating its previous index instead of drawing a new one
+    #[structopt(long, default_value = "0")]
+    repeat_prob: f64,
+    /// Change the accessible part of the memory region every given interval in ms
+    #[structopt(long, requires = "churn-range")]
+    churn_interval: Option<u64>,
+    /// Range of lengths `min..max` the accessible part is picked from on each change
+    #[structopt(long, requires = "churn-interval")]
+    churn_range: Option<ChurnRange>,
+    /// Perform a sequential read pass after every given number of updates on each thread
+    #[structopt(long, requires = "scan-bytes")]
+    scan_every: Option<usize>,
+    /// Length of each sequential read pass, starting at a random page
+    #[structopt(long, parse(try_from_str = parse_size))]
+    scan_bytes: Option<usize>,
+    /// Give each thread an equal private part of the memory region to run the workload on
+    #[structopt(long)]
+    partitioned: bool,
+    /// Seed for the deterministic parts of the workload setup
+    #[structopt(short, long, default_value = "0")]
+    seed: u64,
+    /// Record the sampled accesses to the given file, see `gups trace-dump`
+    #[structopt(long, parse(from_os_str))]
+    record_trace: Option<path::PathBuf>,
+    /// Record only every given number of accesses
+    #[structopt(long, default_value = "1")]
+    record_sample: usize,
+    /// Time every n-th update of each thread, given as `n` or `1/n`, and log the percentiles of the
+    /// latencies in ns at the end of each iteration
+    #[structopt(long, parse(try_from_str = parse_sample))]
+    latency_sample: Option<usize>,
+    /// Time the sampled updates by the TSC calibrated against the monotonic clock
+    #[structopt(long, requires = "latency-sample")]
+    latency_rdtsc: bool,
+    /// Append the buckets of the latency histogram of each iteration to the given CSV file
+    #[structopt(long, requires = "latency-sample", parse(from_os_str))]
+    latency_out: Option<path::PathBuf>,
+    /// Count the given hardware events over each iteration, such as
+    /// `cycles,instructions,LLC-load-misses,dTLB-load-misses,node-load-misses` or raw `r<hex>`
+    #[structopt(long, require_delimiter = true)]
+    perf: Vec<PerfEvent>,
+    /// Write the arguments, the environment and the reports of each iteration to the given file as
+    /// a JSON document on exit
+    #[structopt(long, parse(from_os_str))]
+    json_out: Option<path::PathBuf>,
+    /// Write a row per report to the given CSV file and the DRAM portion of each GB per report to
+    /// the file of the same name with the extension `.dram.csv`, a row per chunk
+    #[structopt(long, parse(from_os_str))]
+    csv_out: Option<path::PathBuf>,
+    /// Write a JSON object per line to the given file for each start and end of an iteration,
+    /// report and change of the hot set, with the monotonic and the wall-clock time
+    #[structopt(long, parse(from_os_str))]
+    events_out: Option<path::PathBuf>,
+    /// Allocate the memory region on the heap, as an anonymous private mapping (`mmap`) or as a
+    /// shared mapping of a file created or resized to the length of the region (`file:<path>`),
+    /// such as on tmpfs or a DAX filesystem, or of a sealed memfd another process can map through
+    /// the logged fd path (`memfd[:name]`)
+    #[structopt(long, default_value = "heap")]
+    backing: Backing,
+    /// Back the memory region with hugetlb pages of the given size, implying `--backing mmap`
+    #[structopt(long, possible_values = &["2m", "1g"])]
+    hugepage: Option<HugePage>,
+    /// Align the start of the memory region and of each of `--regions` to the given number of
+    /// bytes, a huge page of 2M if `--thp always` by default, so that no part of it misses out on
+    /// transparent huge pages
+    #[structopt(long, parse(try_from_str = parse_size))]
+    align: Option<usize>,
+    /// Map the memory region as the given number of adjacent mappings of equal length, each a VMA
+    /// of its own, implying `--backing mmap`
+    #[structopt(long, default_value = "1")]
+    regions: usize,
+    /// Give each thread a private region of the memory region as by `--partitioned`, which a thread
+    /// of its own maps as a separate VMA and initializes, so that its pages are first touched by
+    /// that thread
+    #[structopt(long, conflicts_with = "regions")]
+    private_regions: bool,
+    /// Pin the threads to the CPUs in order and bind the private region of each to the node of its
+    /// CPU, implying `--private-regions`, and report the pages per node of each
+    #[structopt(long, conflicts_with = "regions")]
+    local_alloc: bool,
+    /// Fork the given number of processes each running the workload over the shared memory region
+    /// with its share of the threads and updates, implying `--backing memfd` over the heap, and
+    /// report the gups of each process besides the aggregate
+    #[structopt(long, default_value = "1")]
+    processes: usize,
+    /// Advise the kernel to back the memory region with transparent huge pages or not, regardless of
+    /// the system-wide setting
+    #[structopt(long, default_value = "default", possible_values = &["always", "never", "default"])]
+    thp: Thp,
+    /// Fill the memory region with a byte (`byte:<xx>` in hex), zeros, the bytes of a random
+    /// generator of the given seed (`random:<seed>`) or nothing, leaving its pages to be first
+    /// touched by the workers (`none`)
+    #[structopt(long, default_value = "byte:dd")]
+    init: Init,
+    /// Initialize the memory region in chunks of the given length, making sure before each that the
+    /// available memory holds the rest and logging the progress
+    #[structopt(long, default_value = "1G", parse(try_from_str = parse_size))]
+    alloc_chunk: usize,
+    /// Run with a memory region exceeding the available memory, mapped without reserving swap, and
+    /// log the swap usage of the process in each report
+    #[structopt(long)]
+    allow_overcommit: bool,
+    /// Fault in the pages of the memory region before the first iteration, by `MAP_POPULATE` for
+    /// an anonymous mapping without advice or memory policy and by a parallel pass touching each
+    /// page otherwise. The first iteration is labelled as including the faults if neither this nor
+    /// the initialization touches the pages
+    #[structopt(long)]
+    prefault: bool,
+    /// Discard the pages of the mapped memory region before each iteration after the first, so
+    /// that every iteration starts from unfaulted pages rather than the placement of the previous
+    #[structopt(long)]
+    reset_between_iterations: bool,
+    /// Leave the pages of the mapped memory region to be faulted by the first access of the
+    /// workload as with `--init none`, reporting the page faults of each iteration and the pages
+    /// that became present during it
+    #[structopt(long, conflicts_with_all = &["init", "prefault"])]
+    lazy: bool,
+    /// Track the pages accessed in each interval of the given milliseconds by protecting the memory
+    /// region and unprotecting each page on its first fault, which perturbs the workload. A region
+    /// of more pages than half of `vm.max_map_count` is tracked in aligned groups of pages instead,
+    /// all of which count as accessed on the first fault in any
+    #[structopt(long)]
+    track_protect: Option<u64>,
+    /// Directory of the bitmaps of the pages accessed in each tracked interval, dumped as
+    /// `track-<n>.bin` of little-endian 64-bit words with bit `i` of word `w` for page `64w + i`
+    #[structopt(long, default_value = ".", parse(from_os_str))]
+    track_dir: path::PathBuf,
+    /// Apply the memory policy to the memory region with the given NUMA nodes before its first
+    /// touch, leaving the rest of the process unbound
+    #[structopt(long, require_delimiter = true)]
+    membind: Vec<i32>,
+    /// First touch the memory region in stripes from threads on the given NUMA nodes in turn,
+    /// pinned to the CPUs of a node or preferring a node without CPUs, then log the pages per node
+    #[structopt(long, require_delimiter = true)]
+    init_interleave: Vec<i32>,
+    /// Length of the stripes interleaved by `--init-interleave`, a page by default
+    #[structopt(long, requires = "init-interleave", parse(try_from_str = parse_size))]
+    init_stripe: Option<usize>,
+    /// First touch the hot regions of the hotset workload from a thread on the node `hot=<node>`
+    /// and the rest of the memory region from one on `cold=<node>`, then log the DRAM ratio of
+    /// each
+    #[structopt(long)]
+    first_touch: Option<FirstTouch>,
+    /// Advise the kernel of the use of the hot regions of the hotset workload by `hot=<hint>` and
+    /// of the rest of the memory region by `cold=<hint>`, each hint one of `willneed`,
+    /// `sequential`, `random`, `cold` and `pageout`
+    #[structopt(long)]
+    madvise: Option<Madvise>,
+    /// Advise the kernel by `--madvise` once more after the warm-up iteration
+    #[structopt(long, requires = "madvise")]
+    madvise_after_warmup: bool,
+    /// Lock all of the memory region, the hot regions of the hotset workload or the rest of it into
+    /// memory before the first iteration, unlocking it at the end
+    #[structopt(long, default_value = "none", possible_values = &["all", "hot", "cold", "none"])]
+    mlock: Mlock,
+    /// Run in a cgroup v2 of the given name created next to the current cgroup before the
+    /// allocation and removed on exit, reporting its memory usage after each iteration
+    #[structopt(long)]
+    cgroup: Option<String>,
+    /// Limit the memory of the cgroup by `memory.max`
+    #[structopt(long, requires = "cgroup", parse(try_from_str = parse_size))]
+    memory_max: Option<usize>,
+    /// Throttle the memory of the cgroup beyond `memory.high`
+    #[structopt(long, requires = "cgroup", parse(try_from_str = parse_size))]
+    memory_high: Option<usize>,
+    /// Memory policy of the memory region, `bind` if `--membind` is given
+    #[structopt(long, possible_values = &["bind", "preferred", "interleave", "default"])]
+    mempolicy: Option<Mempolicy>,
+    /// Place the pages of the hot regions on the given NUMA node before the first iteration
+    #[structopt(long)]
+    hot_node: Option<i32>,
+    /// Place the pages outside of the hot regions on the given NUMA node before the first iteration
+    #[structopt(long)]
+    cold_node: Option<i32>,
+    /// Move the pages of byte ranges of the memory region to NUMA nodes after its initialization
+    /// by `<start>..<end>=node<n>,...`, the end being `end` for the end of the memory region,
+    /// and report how many of them landed there
+    #[structopt(long)]
+    place: Option<Placement>,
+    /// Start of a region the random and zipf workloads never access
+    #[structopt(long, requires = "hole-len", parse(try_from_str = parse_size))]
+    hole_offset: Option<usize>,
+    /// Length of the region never accessed
+    #[structopt(long, requires = "hole-offset", parse(try_from_str = parse_size))]
+    hole_len: Option<usize>,
     #[structopt(subcommand)]
     workload: Workload,
 }
 
-#[derive(StructOpt, Debug, Clone, Copy)]
+/// Prints a trace recorded with `--record-trace` as CSV lines of `index,op,thread,seq`, which the
+/// replay workload accepts as is.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "gups trace-dump")]
+struct TraceDump {
+    /// Path to the recorded trace
+    #[structopt(parse(from_os_str))]
+    trace: path::PathBuf,
+}
+
+/// Renders a dump of `--residency-dump` on stdout, either as a PGM image with a pixel per `scale`
+/// pages, white in DRAM, grey present elsewhere and black absent, or as CSV lines of
+/// `page,dram,present` with the portions of the pages of each pixel.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "gups heatmap-render")]
+struct HeatmapRender {
+    /// Path to the residency dump
+    #[structopt(parse(from_os_str))]
+    dump: path::PathBuf,
+    #[structopt(long, default_value = "pgm", possible_values = &["pgm", "csv"])]
+    format: String,
+    /// Pages per pixel, averaged
+    #[structopt(long, default_value = "1")]
+    scale: usize,
+    /// Pixels per row of the image
+    #[structopt(long, default_value = "1024")]
+    width: usize,
+}
+
+impl Args {
+    /// Checks the sizes against each other, listing every constraint they violate.
+    fn validate(&self) -> Result<()> {
+        let (len, g) = (self.region_len(), self.granularity.unit());
+        let mut violations = vec![];
+        if ![1, 2, 4, 8, 16].contains(&g) {
+            violations.push(format!(
+                "granularity {g} is not one of 1, 2, 4, 8 or 16 bytes"
+            ));
+        } else if self.len % g != 0 {
+            violations.push(format!(
+                "length {} is not a multiple of the granularity {g}",
+                self.len
+            ));
+        }
+        match len / g.max(1) {
+            0 => violations.push(format!(
+                "memory region of {len} bytes holds no element of {g} bytes"
+            )),
+            // the distributions sample in floating point
+            n if n as u64 > 1 << f64::MANTISSA_DIGITS => violations.push(format!(
+                "memory region of {n} elements exceeds the 2^{} the distributions sample exactly",
+                f64::MANTISSA_DIGITS
+            )),
+            _ => {}
+        }
+        let page = self.page_size();
+        // sampled from a single read of the pagemap
+        let sampled = [
+            ("dram-ratio", self.dram_ratio),
+            ("soft-dirty", self.soft_dirty),
+            ("idle-track", self.idle_track),
+        ];
+        let sampled: Vec<_> = sampled.iter().filter_map(|&(n, i)| Some((n, i?))).collect();
+        for pair in sampled.windows(2) {
+            let [(a, i), (b, j)] = [pair[0], pair[1]];
+            if i != j {
+                violations.push(format!("{b} interval {j} differs from {a} interval {i}"));
+            }
+        }
+        if self.cdf_chunk == 0 {
+            violations.push("cdf chunk must hold at least a byte".to_owned());
+        }
+        if self.check_dist && self.check_dist_buckets == 0 {
+            violations.push("distribution check needs at least a bucket".to_owned());
+        }
+        if self.dram_chunk == 0 || self.dram_chunk % page != 0 {
+            violations.push(format!(
+                "dram chunk of {} bytes is not a multiple of the page size {page}",
+                self.dram_chunk
+            ));
+        }
+        if let Workload::Hotset { hot, .. } = &self.workload {
+            let total = hot.iter().fold(0usize, |total, &h| total.saturating_add(h));
+            if total > len {
+                violations.push(format!(
+                    "hot regions of {total} bytes exceed the memory region of {len} bytes"
+                ));
+            }
+        }
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(format!("invalid arguments: {}", violations.join("; ")).into()),
+        }
+    }
+
+    /// Length of the memory region the workload distribution covers, which is that of a
+    /// single thread's part when partitioned, in whole alignments for private regions.
+    fn region_len(&self) -> usize {
+        if self.private_regions {
+            self.len / self.thread / self.align() * self.align()
+        } else if self.partitioned {
+            let g = self.granularity.unit();
+            self.len / self.thread / g * g
+        } else {
+            self.len
+        }
+    }
+
+    /// Size of the pages backing the memory region.
+    fn page_size(&self) -> usize {
+        self.hugepage.map_or(*PAGE_SIZE, HugePage::size)
+    }
+
+    /// The expected bytes read and written by each update given the op, the read ratio and the
+    /// size classes, unless the workload does not take them.
+    fn bytes_per_update(&self) -> Option<(f64, f64)> {
+        if let Workload::Chase {} | Workload::Hpcc {} | Workload::Replay { .. } = self.workload {
+            return None;
+        }
+        let total = self.granularity.weights.iter().sum::<usize>() as f64;
+        let read = self.read_ratio as f64 / 100.0;
+        let (mut r, mut w) = (0.0, 0.0);
+        for (&size, &weight) in self.granularity.sizes.iter().zip(&self.granularity.weights) {
+            let size = match self.op {
+                Op::Copy => self.copy_size,
+                _ => size,
+            };
+            let (loaded, stored) = self.op.bytes(size);
+            let weight = weight as f64 / total;
+            r += weight * (read * size as f64 + (1.0 - read) * loaded as f64);
+            w += weight * (1.0 - read) * stored as f64;
+        }
+        Some((r, w))
+    }
+
+    /// The counters of `/proc/vmstat` shown by `--vmstat`.
+    fn vmstat_fields(&self) -> Vec<String> {
+        let extra = self.vmstat_fields.iter().cloned();
+        VMSTAT_FIELDS
+            .map(str::to_owned)
+            .into_iter()
+            .chain(extra)
+            .collect()
+    }
+
+    /// Alignment of the memory region, at least a page.
+    fn align(&self) -> usize {
+        let thp = match self.thp {
+            Thp::Always => HugePage::M2.size(),
+            _ => 1,
+        };
+        self.align.unwrap_or(thp).max(self.page_size())
+    }
+
+    /// Memory policy of the memory region.
+    fn mempolicy(&self) -> Mempolicy {
+        match (self.mempolicy, self.membind.is_empty()) {
+            (Some(policy), _) => policy,
+            (None, false) => Mempolicy::Bind,
+            (None, true) => Mempolicy::Default,
+        }
+    }
+
+    /// Elements never accessed, widened to whole elements and empty without a hole.
+    fn hole(&self) -> Result<ops::Range<usize>> {
+        let (offset, len) = match self.hole_offset.zip(self.hole_len) {
+            Some(hole) => hole,
+            None => return Ok(0..0),
+        };
+        let g = self.granularity.unit();
+        let hole = offset / g..(offset + len).div_ceil(g);
+        let nelems = self.region_len() / g;
+        if hole.end > nelems || hole.len() >= nelems {
+            return Err(format!(
+                "hole of {len} bytes at {offset} leaves no memory region of {} bytes",
+                self.region_len()
+            )
+            .into());
+        }
+        Ok(hole)
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(StructOpt, Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
 enum Workload {
-    /// Two random access region with fixed access frequency ratio
+    /// Random access hot regions and the rest with fixed access frequency ratios
     Hotset {
-        /// Length of the hot memory region
-        #[structopt(short, long)]
-        hot: usize,
-        /// Weight ratio of hot region to the rest
-        #[structopt(short, long)]
-        weight: usize,
+        /// Length of each hot memory region
+        #[structopt(
+            short,
+            long,
+            use_delimiter = true,
+            required_unless_one = &["regions", "hot-pct"],
+            conflicts_with = "hot-pct",
+            parse(try_from_str = parse_size)
+        )]
+        #[serde(default)]
+        hot: Vec<usize>,
+        /// Length of each hot memory region as a percentage of the memory region
+        #[structopt(long, use_delimiter = true)]
+        #[serde(default)]
+        hot_pct: Vec<f64>,
+        /// Weight ratio of each hot region to the rest
+        #[structopt(short, long, use_delimiter = true, required_unless = "regions")]
+        #[serde(default)]
+        weight: Vec<usize>,
+        /// Named regions laid out in order as `name:size:weight`, such as
+        /// `hot:1G:100,warm:4G:10,cold:rest:1`, in place of the hot regions and the rest
+        #[structopt(
+            long,
+            use_delimiter = true,
+            conflicts_with_all = &["hot", "hot-pct", "weight", "offsets", "offset-pct"]
+        )]
+        #[serde(default)]
+        regions: Vec<RegionSpec>,
+        /// Start of each hot memory region, evenly spaced over the memory region if omitted
+        #[structopt(
+            short,
+            long,
+            alias = "hot-offset",
+            use_delimiter = true,
+            conflicts_with = "offset-pct",
+            parse(try_from_str = parse_size)
+        )]
+        #[serde(default, alias = "hot-offset")]
+        offsets: Vec<usize>,
+        /// Start of each hot memory region as a percentage of the memory region
+        #[structopt(long, use_delimiter = true)]
+        #[serde(default)]
+        offset_pct: Vec<f64>,
+        /// Slide the hot regions forward every given interval in ms
+        #[structopt(long)]
+        shift_interval: Option<u64>,
+        /// Grow the single hot region by the given bytes per second up to the end of the memory
+        /// region
+        #[structopt(long, conflicts_with = "hot-shrink", parse(try_from_str = parse_size))]
+        hot_grow: Option<usize>,
+        /// Shrink the single hot region by the given bytes per second down to a single element
+        #[structopt(long, parse(try_from_str = parse_size))]
+        hot_shrink: Option<usize>,
+        /// Length of each slide of the hot regions
+        #[structopt(long, default_value = "0", parse(try_from_str = parse_size))]
+        #[serde(default)]
+        shift_step: usize,
+        /// Restrict writes to the hot or cold regions while reads still cover both, logging the bytes
+        /// read and written in each
+        #[structopt(long, default_value = "both", possible_values = &["hot", "cold", "both"])]
+        #[serde(default)]
+        write_region: WriteRegion,
+        /// Cycle the weight of each hot region through the given `weight:secs` steps
+        #[structopt(long, use_delimiter = true)]
+        #[serde(default)]
+        weight_schedule: Vec<WeightStep>,
+        /// Swap the hot and cold regions once the given fraction of the updates has been issued
+        #[structopt(long)]
+        #[serde(default)]
+        flip_at: Option<f64>,
         /// Reverse the allocation of hot set and cold set
         #[structopt(short, long)]
+        #[serde(default)]
         reverse: bool,
     },
     /// Zipfian distribution
@@ -63,317 +609,8231 @@ enum Workload {
         exponent: f64,
         /// Index from the end of the memory region backwords
         #[structopt(short, long)]
+        #[serde(default)]
         reverse: bool,
+        /// Map the ranks to elements through a permutation seeded by `--seed`, so that hot elements
+        /// are scattered over the memory region
+        #[structopt(long, conflicts_with = "reverse")]
+        #[serde(default)]
+        scramble: bool,
+        /// Replace the last iteration by one per given exponent over the same memory
+        #[structopt(long, use_delimiter = true)]
+        #[serde(default)]
+        exponent_sweep: Vec<f64>,
     },
     /// Random distribution
-    Random {},
+    Random {
+        /// Index from the end of the memory region backwords
+        #[structopt(short, long)]
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// Sequential scan over the whole memory region
+    Sequential {
+        /// Scan from the end of the memory region backwards
+        #[structopt(short, long)]
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// Fixed stride access wrapping around the memory region
+    Strided {
+        /// Distance in elements between two consecutive updates
+        #[structopt(short, long)]
+        stride: usize,
+        /// Index of the first element to update
+        #[structopt(short, long, default_value = "0")]
+        #[serde(default)]
+        offset: usize,
+    },
+    /// Pointer chasing along a random cycle through all elements of at least 16 bytes, the link
+    /// in the first 8 and a visit counter in the next
+    Chase {},
+    /// HPCC RandomAccess, xoring the LCG sequence into a power-of-two table of 64-bit words
+    Hpcc {},
+    /// Replay the element indices of a trace in order, each thread taking a contiguous block, in
+    /// place of the given number of updates
+    Replay {
+        /// Path to the trace, lines of `index[,r|w]` if it ends in `.csv` and little-endian 64-bit
+        /// indices otherwise
+        #[structopt(parse(from_os_str))]
+        trace: path::PathBuf,
+        /// Binary records are followed by an op byte, 0 for a write and 1 for a read
+        #[structopt(long)]
+        #[serde(default)]
+        op: bool,
+        /// Take out-of-range indices modulo the number of elements instead of dropping them
+        #[structopt(long)]
+        #[serde(default)]
+        wrap: bool,
+    },
+    /// Run the workload phases listed in a TOML file one after another
+    #[serde(skip_deserializing)]
+    Schedule {
+        /// Path to the schedule file
+        #[structopt(parse(from_os_str))]
+        file: path::PathBuf,
+    },
+    /// Normal distribution truncated to the memory region
+    Normal {
+        /// Mean as a fraction of the memory region, i.e. the center of the hotspot
+        #[structopt(short, long, alias = "center")]
+        #[serde(alias = "center")]
+        mean_frac: f64,
+        /// Standard deviation as a fraction of the memory region, i.e. the spread of the hotspot
+        #[structopt(short, long, alias = "spread")]
+        #[serde(alias = "spread")]
+        stddev_frac: f64,
+        /// Index from the end of the memory region backwords
+        #[structopt(short, long)]
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// Exponentially decaying distribution truncated to the memory region, draws beyond the end
+    /// of the memory region are re-sampled
+    Exp {
+        /// Decay rate over the length of the memory region, i.e. the first element is e^lambda
+        /// times as hot as the last one
+        #[structopt(short, long)]
+        lambda: f64,
+        /// Index from the end of the memory region backwords
+        #[structopt(short, long)]
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// Pareto distribution truncated to the memory region
+    Pareto {
+        /// The shape parameter of pareto distribution
+        #[structopt(short, long)]
+        shape: f64,
+        /// Index from the end of the memory region backwords
+        #[structopt(short, long)]
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// YCSB latest, re-accessing the last accessed elements of the thread by zipf distributed rank
+    Latest {
+        /// The parameter of zipf distribution over the recent elements
+        #[structopt(short, long)]
+        exponent: f64,
+        /// Number of recently accessed elements each thread remembers
+        #[structopt(short, long)]
+        window: usize,
+    },
+    /// Random walk of each thread, moving by up to `step` elements either way on every update
+    Walk {
+        /// Largest distance in elements of a single move
+        #[structopt(short, long)]
+        step: usize,
+    },
+    /// Zipfian distribution over the pages, uniform within each page
+    PageZipf {
+        /// The parameter of zipf distribution
+        #[structopt(short, long)]
+        exponent: f64,
+        /// Index the pages from the end of the memory region backwords
+        #[structopt(short, long)]
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// A distribution per group of threads over the same memory region
+    Tenants {
+        /// Groups of inclusive thread ranges with their distributions covering all threads, such as
+        /// `0-7:zipf:0.99,8-15:random` or `0-3:self-similar:0.8,4-7:random`
+        #[structopt(long, use_delimiter = true, required = true)]
+        per_thread_workload: Vec<ThreadGroup>,
+    },
+    /// Uniform centers, each updated along with its neighbors up to `radius` elements away
+    Stencil {
+        /// Number of neighbors updated on either side of each center, fewer at the boundaries
+        #[structopt(short, long)]
+        radius: usize,
+    },
+    /// Self-similar b-model sending `bias` of the accesses to the lower half of every range
+    SelfSimilar {
+        /// Fraction of the accesses to the lower half at each level, in [0.5, 1)
+        #[structopt(short, long)]
+        bias: f64,
+        /// Index from the end of the memory region backwords
+        #[structopt(short, long)]
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// Markov chain over equal segments, uniform within each segment
+    Markov {
+        /// Path to the row-stochastic transition matrix, rows of comma separated probabilities if it
+        /// ends in `.csv` and a TOML `matrix` array of rows otherwise
+        #[structopt(parse(from_os_str))]
+        matrix: path::PathBuf,
+    },
+    /// Arbitrary byte ranges with weights, uniform within each
+    Spans {
+        /// Disjoint half-open byte ranges with weights such as `0..1G:50,1G..5G:5,5G..end:1`
+        #[structopt(long, use_delimiter = true, required = true)]
+        spans: Vec<SpanSpec>,
+    },
+    /// Bursts of updates to a random page-aligned cluster before each thread moves on to the next
+    Cluster {
+        /// Length of each cluster, a multiple of the granularity. Clusters start on page
+        /// boundaries and run on into the following pages when longer than a page
+        #[structopt(short, long)]
+        cluster_size: usize,
+        /// Number of updates to each cluster
+        #[structopt(short, long)]
+        burst: usize,
+    },
 }
 
-fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
-    let args = Args::from_args();
-    tracing::info!("gups args {args:?}");
-    if args.dram_ratio.unwrap_or(u64::MAX) != u64::MAX {
-        // ensure the DRAM_PFN_RANGE is initialized
-        let _ = *DRAM_PFN_RANGE;
+impl Workload {
+    /// Resolves the sizes given as percentages of the memory region of `len` bytes into bytes
+    /// rounded down to the granularity `g`.
+    fn resolve_pct(&mut self, len: usize, g: usize) -> Result<()> {
+        if let Workload::Hotset {
+            hot,
+            hot_pct,
+            offsets,
+            offset_pct,
+            ..
+        } = self
+        {
+            for (name, bytes, pct) in [("hot", hot, hot_pct), ("offset", offsets, offset_pct)] {
+                if pct.is_empty() {
+                    continue;
+                }
+                if !bytes.is_empty() {
+                    return Err(format!("both {name} and {name} percentages given").into());
+                }
+                for &p in pct.iter() {
+                    if !(0.0..=100.0).contains(&p) {
+                        return Err(format!("{name} percentage {p} out of range [0, 100]").into());
+                    }
+                    bytes.push((p / 100.0 * len as f64) as usize / g * g);
+                }
+                tracing::info!("{name} percentages {pct:?} resolved to {bytes:?} bytes");
+            }
+        }
+        Ok(())
     }
-    let mem = vec![0xddu8; args.len].into_boxed_slice();
-    tracing::info!("memory {:?} length {:?}", mem.as_ptr(), mem.len());
-    async_std::task::block_on(main_loop(args, Arc::new(sync::RwLock::new(mem))))?;
-    Ok(())
 }
 
-async fn main_loop(args: Args, mem: Arc<sync::RwLock<Box<[u8]>>>) -> Result<()> {
-    // warm-up
-    tracing::info!("warm up iteration start");
-    iteration("first", args, mem.clone()).await?;
-    // second
-    tracing::info!("second iteration start");
-    iteration("warm up", args, mem.clone()).await?;
-    // final
-    tracing::info!("third iteration start");
-    iteration("last", args, mem.clone()).await?;
-
-    Ok(())
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum WriteRegion {
+    Hot,
+    Cold,
+    #[default]
+    Both,
+}
+impl std::str::FromStr for WriteRegion {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "hot" => Ok(Self::Hot),
+            "cold" => Ok(Self::Cold),
+            "both" => Ok(Self::Both),
+            _ => Err(format!("unknown region {s}")),
+        }
+    }
 }
 
-async fn iteration(label: &str, args: Args, mem: Arc<sync::RwLock<Box<[u8]>>>) -> Result<()> {
-    let (count_tx, count_rx) = mpsc::unbounded();
-    let region = {
-        let ptr = mem.read().unwrap().as_ptr();
-        mem_region(ptr as _)
-    };
-    join!(
-        async_std::task::spawn_blocking(move || gups_worker(args, mem, count_tx).unwrap()),
-        reporting_actor(
-            label,
-            count_rx,
-            time::Duration::from_millis(u64::MAX.min(args.report.unwrap_or(u64::MAX))),
-            time::Duration::from_millis(u64::MAX.min(args.dram_ratio.unwrap_or(u64::MAX))),
-            region,
-        )
-    );
-    Ok(())
+/// Operation performed by the updates of the workloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+enum Op {
+    Add,
+    Xor,
+    Store,
+    Load,
+    Copy,
+}
+impl std::str::FromStr for Op {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "add" => Ok(Self::Add),
+            "xor" => Ok(Self::Xor),
+            "store" => Ok(Self::Store),
+            "load" => Ok(Self::Load),
+            "copy" => Ok(Self::Copy),
+            _ => Err(format!("unknown op {s}")),
+        }
+    }
+}
+impl Op {
+    /// Bytes read and written by an update of `size` bytes, both for a read-modify-write. The
+    /// source of `Copy` is a per-thread buffer kept in cache.
+    fn bytes(self, size: usize) -> (usize, usize) {
+        match self {
+            Op::Add | Op::Xor => (size, size),
+            Op::Store | Op::Copy => (0, size),
+            Op::Load => (size, 0),
+        }
+    }
 }
 
-pub struct Mod<T, U, X: Copy>
-where
-    T: Distribution<U>,
-    U: ops::Rem<X, Output = U>,
-{
-    distribution: T,
-    len: X,
-    _marker: marker::PhantomData<U>,
+/// Unit of the updates per second shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+enum Unit {
+    Gups,
+    Mups,
+    Ops,
 }
-impl<T, U, X: Copy> Mod<T, U, X>
-where
-    T: Distribution<U>,
-    U: ops::Rem<X, Output = U>,
-{
-    pub fn new(distribution: T, len: X) -> Self {
-        Self {
-            distribution,
-            len,
-            _marker: marker::PhantomData,
+impl std::str::FromStr for Unit {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "gups" => Ok(Self::Gups),
+            "mups" => Ok(Self::Mups),
+            "ops" => Ok(Self::Ops),
+            _ => Err(format!("unknown unit {s}")),
         }
     }
 }
-impl<T, U, X: Copy> Distribution<U> for Mod<T, U, X>
-where
-    T: Distribution<U>,
-    U: ops::Rem<X, Output = U>,
-{
-    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> U {
-        self.distribution.sample(rng) % self.len
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Unit::Gups => "GUPS",
+            Unit::Mups => "MUPS",
+            Unit::Ops => "ops/s",
+        })
     }
 }
+impl Unit {
+    /// Updates per second of the unit, decimal rather than binary.
+    fn scale(self) -> f64 {
+        match self {
+            Unit::Gups => 1e9,
+            Unit::Mups => 1e6,
+            Unit::Ops => 1.0,
+        }
+    }
 
-pub struct Backwards<T, U, M>
-where
-    T: Distribution<U>,
-    M: ops::Sub<U, Output = U> + Copy,
-{
-    distribution: T,
-    minuend: M,
-    _marker: marker::PhantomData<U>,
+    /// The given updates over the given seconds in this unit.
+    fn rate(self, updates: usize, secs: f64) -> f64 {
+        updates as f64 / secs / self.scale()
+    }
+
+    /// Formats a rate in this unit down to the thousands of updates per second.
+    fn show(self, rate: f64) -> String {
+        let digits = (self.scale().log10() as usize).saturating_sub(3);
+        format!("{rate:.digits$}")
+    }
 }
-impl<T, U, M> Backwards<T, U, M>
-where
-    T: Distribution<U>,
-    M: ops::Sub<U, Output = U> + Copy,
-{
-    pub fn new(distribution: T, minuend: M) -> Self {
-        Self {
-            distribution,
-            minuend,
-            _marker: marker::PhantomData,
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum Backing {
+    Heap,
+    Mmap,
+    File(path::PathBuf),
+    Memfd(String),
+}
+impl std::str::FromStr for Backing {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "heap" => Ok(Self::Heap),
+            "mmap" => Ok(Self::Mmap),
+            "memfd" => Ok(Self::Memfd("gups".into())),
+            _ => match s.split_once(':') {
+                Some(("file", path)) if !path.is_empty() => Ok(Self::File(path.into())),
+                Some(("memfd", name)) if !name.is_empty() => Ok(Self::Memfd(name.into())),
+                _ => Err(format!("unknown backing {s}")),
+            },
         }
     }
 }
-impl<T, U, M> Distribution<U> for Backwards<T, U, M>
-where
-    T: Distribution<U>,
-    M: ops::Sub<U, Output = U> + Copy,
-{
-    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> U {
-        self.minuend - self.distribution.sample(rng)
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+enum HugePage {
+    M2,
+    G1,
+}
+impl HugePage {
+    /// The base 2 logarithm of the page size, as taken by `MAP_HUGE_SHIFT`.
+    fn bits(self) -> u8 {
+        match self {
+            HugePage::M2 => 21,
+            HugePage::G1 => 30,
+        }
+    }
+
+    fn size(self) -> usize {
+        1 << self.bits()
+    }
+
+    /// Free and total pages of the hugetlb pool of this size.
+    fn pool(self) -> Result<(usize, usize)> {
+        let dir = format!("/sys/kernel/mm/hugepages/hugepages-{}kB", self.size() >> 10);
+        let read = |name| -> Result<usize> {
+            let path = path::Path::new(&dir).join(name);
+            Ok(std::fs::read_to_string(&path)
+                .map_err(|e| format!("{}: {e}", path.display()))?
+                .trim()
+                .parse()?)
+        };
+        Ok((read("free_hugepages")?, read("nr_hugepages")?))
+    }
+}
+impl std::str::FromStr for HugePage {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "2m" => Ok(Self::M2),
+            "1g" => Ok(Self::G1),
+            _ => Err(format!("unknown hugepage size {s}")),
+        }
     }
 }
 
-fn gups_worker(
-    args: Args,
-    mem: Arc<sync::RwLock<Box<[u8]>>>,
-    count: mpsc::UnboundedSender<usize>,
-) -> Result<()> {
-    let (updates, thread, len, g) = (args.update, args.thread, args.len, args.granularity);
-    let end = args.len / args.granularity;
-    let mem = &mut **mem.write().unwrap();
-    match args.workload {
-        Workload::Hotset {
-            hot,
-            weight,
-            reverse: r,
-        } => {
-            let split = hot / g;
-            let v = [Uniform::new(0, split), Uniform::new(split, end)];
-            let d = Mod::new(Mix::new(v, [weight, 1]).unwrap(), end);
-            if r {
-                gups_do(updates, thread, g, mem, Backwards::new(d, end - 1), count)?;
-            } else {
-                gups_do(updates, thread, g, mem, d, count)?;
+/// Split of the memory region into `--regions` mappings of `len` bytes each, the last one possibly
+/// partially used.
+#[derive(Debug, Clone, Copy)]
+struct RegionLayout {
+    count: usize,
+    len: usize,
+    /// Bits of `len` if a power of two.
+    shift: Option<u32>,
+}
+impl RegionLayout {
+    fn new(args: &Args) -> Self {
+        let count = args.regions.max(1);
+        let len = match args.private_regions {
+            true => args.region_len(),
+            false => args.len.div_ceil(count).next_multiple_of(args.align()),
+        };
+        let shift = len.is_power_of_two().then(|| len.trailing_zeros());
+        Self { count, len, shift }
+    }
+
+    /// The index of the region holding the byte at `offset` and the offset within it.
+    #[inline(always)]
+    fn locate(&self, offset: usize) -> (usize, usize) {
+        match self.shift {
+            Some(shift) => (offset >> shift, offset & (self.len - 1)),
+            None => (offset / self.len, offset % self.len),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+enum Init {
+    Byte(u8),
+    Zero,
+    Random(u64),
+    None,
+}
+impl std::str::FromStr for Init {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once(':') {
+            _ if s == "zero" => Ok(Self::Zero),
+            _ if s == "none" => Ok(Self::None),
+            Some(("byte", b)) => {
+                let b = b.trim_start_matches("0x");
+                u8::from_str_radix(b, 16)
+                    .map(Self::Byte)
+                    .map_err(|e| format!("invalid byte {b}: {e}"))
             }
+            Some(("random", seed)) => seed
+                .parse()
+                .map(Self::Random)
+                .map_err(|e| format!("invalid seed {seed}: {e}")),
+            _ => Err(format!("unknown init {s}")),
         }
-        Workload::Zipf {
-            exponent,
-            reverse: r,
-        } => {
-            let nelems = len / g;
-            let d = ZipfDistribution::new(nelems, exponent).unwrap();
-            if r {
-                gups_do(
-                    updates,
-                    thread,
-                    g,
-                    mem,
-                    Backwards::new(d, nelems - 1),
-                    count,
-                )?;
-            } else {
-                gups_do(updates, thread, g, mem, d, count)?;
+    }
+}
+impl Init {
+    /// Fills the part of the memory region starting `offset` bytes into it. The random bytes only
+    /// depend on their offset, so that filling the region in parts gives the same contents.
+    fn fill(self, mem: &mut [u8], offset: usize) {
+        match self {
+            Init::Byte(b) => mem.fill(b),
+            Init::Zero => mem.fill(0),
+            Init::Random(seed) => {
+                let word = |i: usize| splitmix64(seed ^ (i / 8) as u64).to_le_bytes();
+                // the bytes up to the first whole word, the whole words and the rest
+                let head = (offset.next_multiple_of(8) - offset).min(mem.len());
+                let (head, rest) = mem.split_at_mut(head);
+                for (i, b) in head.iter_mut().enumerate() {
+                    *b = word(offset + i)[(offset + i) % 8];
+                }
+                let start = offset + head.len();
+                let (words, tail) = rest.split_at_mut(rest.len() / 8 * 8);
+                for (i, w) in words.chunks_exact_mut(8).enumerate() {
+                    w.copy_from_slice(&word(start + i * 8));
+                }
+                let start = start + words.len();
+                for (i, b) in tail.iter_mut().enumerate() {
+                    *b = word(start + i)[i];
+                }
             }
+            Init::None => {}
+        }
+    }
+}
+
+/// Initializes the memory region by `--init` in chunks of `--alloc-chunk` bytes, logging the
+/// progress. Unless already faulted or overcommitted, its pages yet to be touched must fit into the
+/// available memory before each chunk.
+fn init_chunked(args: &Args, mem: &mut [u8]) -> Result<()> {
+    let (len, chunk) = (mem.len(), args.alloc_chunk);
+    let faulted = args.prefault || args.hugepage.is_some() || args.allow_overcommit;
+    let start = time::Instant::now();
+    for (k, part) in mem.chunks_mut(chunk).enumerate() {
+        let done = k * chunk;
+        if !faulted {
+            check_available(len - done)?;
         }
-        Workload::Random {} => {
-            let d = Uniform::new(0, end);
-            gups_do(updates, thread, g, mem, d, count)?;
+        args.init.fill(part, done);
+        if len > chunk {
+            let (done, elapsed) = (done + part.len(), start.elapsed());
+            let rate = done as f64 / elapsed.as_secs_f64() / 1e9;
+            tracing::info!("init {done} of {len} bytes at {rate:.3} GB/s");
         }
     }
     Ok(())
 }
 
-thread_local! {
-    static MEM: RefCell<&'static mut [u8]> = RefCell::default();
+/// Fails unless `MemAvailable` of `/proc/meminfo` holds the given bytes.
+fn check_available(bytes: usize) -> Result<()> {
+    let available = status_kb("/proc/meminfo", "MemAvailable")? * 1024;
+    match bytes.checked_sub(available) {
+        Some(excess) if excess > 0 => Err(format!(
+            "initializing {bytes} bytes would exceed available memory of {available} bytes by {excess}"
+        )
+        .into()),
+        _ => Ok(()),
+    }
 }
-fn gups_do<D: Distribution<usize> + Sync>(
-    updates: usize,
-    thread: usize,
-    granularity: usize,
-    mem: &mut [u8],
-    dist: D,
-    count_tx: mpsc::UnboundedSender<usize>,
-) -> Result<()> {
-    let chunk_size = 4096;
-    let do_init = || {
-        // FIXME: We should be initializing each thread with a disjoint part of the memory
-        MEM.with(|m| {
-            let ptr = mem.as_ptr() as *mut _;
-            let mem = unsafe { slice::from_raw_parts_mut(ptr, mem.len()) };
-            m.replace(mem);
-        });
-    };
-    let do_work = || {
-        (0..updates)
-            .into_par_iter()
-            .map_init(rand::thread_rng, |rng, _| dist.sample(rng))
-            .chunks(chunk_size)
-            .for_each(|indices| {
-                MEM.with(|m| {
-                    let mem = &mut **m.borrow_mut();
-                    indices.iter().for_each(|&index| {
-                        update(mem, granularity, index);
+
+/// The kB of the given field of a file like `/proc/meminfo` or `/proc/self/status`.
+fn status_kb(path: &str, field: &str) -> Result<usize> {
+    let status = std::fs::read_to_string(path)?;
+    Ok(status
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+        .and_then(|kb| kb.trim().strip_suffix("kB")?.trim().parse().ok())
+        .ok_or_else(|| format!("no {field} in {path}"))?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+enum Thp {
+    Always,
+    Never,
+    Default,
+}
+impl std::str::FromStr for Thp {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            "default" => Ok(Self::Default),
+            _ => Err(format!("unknown thp mode {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+enum Mempolicy {
+    Bind,
+    Preferred,
+    Interleave,
+    Default,
+}
+impl std::str::FromStr for Mempolicy {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "bind" => Ok(Self::Bind),
+            "preferred" => Ok(Self::Preferred),
+            "interleave" => Ok(Self::Interleave),
+            "default" => Ok(Self::Default),
+            _ => Err(format!("unknown memory policy {s}")),
+        }
+    }
+}
+impl Mempolicy {
+    /// Mode of `mbind`.
+    fn mode(self) -> libc::c_int {
+        match self {
+            Self::Default => 0,
+            Self::Preferred => 1,
+            Self::Bind => 2,
+            Self::Interleave => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+enum Mlock {
+    All,
+    Hot,
+    Cold,
+    None,
+}
+impl std::str::FromStr for Mlock {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "hot" => Ok(Self::Hot),
+            "cold" => Ok(Self::Cold),
+            "none" => Ok(Self::None),
+            _ => Err(format!("unknown mlock mode {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+enum Hint {
+    WillNeed,
+    Sequential,
+    Random,
+    Cold,
+    PageOut,
+}
+impl std::str::FromStr for Hint {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "willneed" => Ok(Self::WillNeed),
+            "sequential" => Ok(Self::Sequential),
+            "random" => Ok(Self::Random),
+            "cold" => Ok(Self::Cold),
+            "pageout" => Ok(Self::PageOut),
+            _ => Err(format!("unknown madvise hint {s}")),
+        }
+    }
+}
+impl Hint {
+    fn advice(self) -> libc::c_int {
+        match self {
+            Self::WillNeed => libc::MADV_WILLNEED,
+            Self::Sequential => libc::MADV_SEQUENTIAL,
+            Self::Random => libc::MADV_RANDOM,
+            Self::Cold => libc::MADV_COLD,
+            Self::PageOut => libc::MADV_PAGEOUT,
+        }
+    }
+}
+
+/// Hints for the hot and cold regions, parsed from `hot=<hint>,cold=<hint>` with either part
+/// optional.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+struct Madvise {
+    hot: Option<Hint>,
+    cold: Option<Hint>,
+}
+impl std::str::FromStr for Madvise {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut madvise = Madvise {
+            hot: None,
+            cold: None,
+        };
+        for part in s.split(',') {
+            let (name, hint) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected hot=<hint> or cold=<hint>, got {part}"))?;
+            match name {
+                "hot" => madvise.hot = Some(hint.parse()?),
+                "cold" => madvise.cold = Some(hint.parse()?),
+                _ => return Err(format!("unknown region {name}")),
+            }
+        }
+        Ok(madvise)
+    }
+}
+
+/// Nodes to first touch the hot and cold regions from, parsed from `hot=<node>,cold=<node>` with
+/// either part optional.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+struct FirstTouch {
+    hot: Option<i32>,
+    cold: Option<i32>,
+}
+impl std::str::FromStr for FirstTouch {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut touch = FirstTouch {
+            hot: None,
+            cold: None,
+        };
+        for part in s.split(',') {
+            let (name, node) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected hot=<node> or cold=<node>, got {part}"))?;
+            let node = match node.parse::<i32>() {
+                Ok(node) if node >= 0 => node,
+                _ => return Err(format!("invalid node {node}")),
+            };
+            match name {
+                "hot" => touch.hot = Some(node),
+                "cold" => touch.cold = Some(node),
+                _ => return Err(format!("unknown region {name}")),
+            }
+        }
+        Ok(touch)
+    }
+}
+
+/// Byte ranges of the memory region to move to NUMA nodes, parsed from
+/// `<start>..<end>=node<n>,...` with sizes like `2G` and `end` for the end of the memory region.
+#[derive(Debug, Clone, serde::Serialize)]
+struct Placement(Vec<(usize, Option<usize>, i32)>);
+impl std::str::FromStr for Placement {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        s.split(',')
+            .map(|part| {
+                let (range, node) = part
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected <start>..<end>=node<n>, got {part}"))?;
+                let (start, end) = range
+                    .split_once("..")
+                    .ok_or_else(|| format!("expected <start>..<end>, got {range}"))?;
+                let end = match end {
+                    "end" => None,
+                    end => Some(parse_size(end)?),
+                };
+                let node = match node.strip_prefix("node").unwrap_or(node).parse::<i32>() {
+                    Ok(node) if node >= 0 => node,
+                    _ => return Err(format!("invalid node {node}")),
+                };
+                Ok((parse_size(start)?, end, node))
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map(Placement)
+    }
+}
+
+/// `<name>=node<n>[+node<n>...],...` naming the tiers of the nodes.
+#[derive(Debug, Clone, serde::Serialize)]
+struct Tiers(Vec<(String, Vec<i32>)>);
+impl std::str::FromStr for Tiers {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let tiers = s
+            .split(',')
+            .map(|part| {
+                let (name, nodes) = part
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected <name>=node<n>, got {part}"))?;
+                if name.is_empty() || ["elsewhere", "absent"].contains(&name) {
+                    return Err(format!("invalid tier name {name}"));
+                }
+                let nodes = nodes
+                    .split('+')
+                    .map(
+                        |node| match node.strip_prefix("node").unwrap_or(node).parse() {
+                            Ok(node) if node >= 0 => Ok(node),
+                            _ => Err(format!("invalid node {node}")),
+                        },
+                    )
+                    .collect::<std::result::Result<_, _>>()?;
+                Ok((name.to_owned(), nodes))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for (k, (name, _)) in tiers.iter().enumerate() {
+            if tiers[..k].iter().any(|(other, _)| other == name) {
+                return Err(format!("tier {name} given twice"));
+            }
+        }
+        Ok(Tiers(tiers))
+    }
+}
+
+/// The memory region, the given range of either a heap allocation or an anonymous private mapping
+/// or a shared file mapping unmapped on drop.
+enum Memory {
+    Heap(Box<[u8]>, ops::Range<usize>),
+    Mmap(memmap2::MmapMut, ops::Range<usize>),
+}
+impl Memory {
+    fn new(args: &Args) -> Result<Self> {
+        let (len, init) = (args.len, args.init != Init::None);
+        // the heap backing is mapped only for hugetlb pages or separate regions
+        let heap = matches!(args.backing, Backing::Heap)
+            && args.hugepage.is_none()
+            && !args.private_regions;
+        if !init && heap && args.regions == 1 {
+            return Err(format!(
+                "{} needs a mapping, as the heap is written on allocation",
+                match args.lazy {
+                    true => "--lazy",
+                    false => "--init none",
+                }
+            )
+            .into());
+        }
+        if args.hugepage.is_some() && args.thp != Thp::Default {
+            return Err("transparent huge pages do not apply to hugetlb".into());
+        }
+        let (policy, nodes) = (args.mempolicy(), &args.membind);
+        match policy {
+            Mempolicy::Default if !nodes.is_empty() => {
+                return Err("the default memory policy takes no --membind nodes".into())
+            }
+            Mempolicy::Preferred if nodes.len() != 1 => {
+                return Err("the preferred memory policy takes a single --membind node".into())
+            }
+            Mempolicy::Bind | Mempolicy::Interleave if nodes.is_empty() => {
+                return Err(
+                    "the bind and interleave memory policies require --membind nodes".into(),
+                )
+            }
+            _ => {}
+        }
+        if let Some(node) = nodes.iter().find(|&&node| node < 0) {
+            return Err(format!("invalid node {node}").into());
+        }
+        if args.first_touch.is_some() {
+            if !init {
+                return Err("--first-touch touches the memory region despite --init none".into());
+            }
+            if !args.init_interleave.is_empty() {
+                return Err(
+                    "--first-touch and --init-interleave both touch the memory region".into(),
+                );
+            }
+            if policy != Mempolicy::Default {
+                return Err("--first-touch is overridden by the memory policy".into());
+            }
+        }
+        if args.prefault && (args.first_touch.is_some() || !args.init_interleave.is_empty()) {
+            return Err("--prefault defeats the placement by first touch".into());
+        }
+        if args.reset_between_iterations {
+            if heap && args.regions == 1 {
+                return Err(
+                    "--reset-between-iterations needs a mapping, as the heap cannot discard its pages"
+                        .into(),
+                );
+            }
+            if args.mlock != Mlock::None {
+                return Err("--reset-between-iterations cannot discard locked pages".into());
+            }
+            if args.first_touch.is_some() || !args.init_interleave.is_empty() {
+                return Err(
+                    "--reset-between-iterations discards the placement by first touch".into(),
+                );
+            }
+            // built once by the setup, the later iterations would chase or look up zeros
+            if let Workload::Chase {} | Workload::Hpcc {} = args.workload {
+                return Err(
+                    "--reset-between-iterations discards the table of chase and hpcc".into(),
+                );
+            }
+            if let Init::Random(_) = args.init {
+                tracing::warn!(
+                    "--reset-between-iterations leaves zeros in place of the random contents"
+                );
+            }
+        }
+        let layout = RegionLayout::new(args);
+        // the private regions leave out the remainder of the memory region
+        let len = match args.private_regions {
+            true => layout.count * layout.len,
+            false => len,
+        };
+        if args.private_regions {
+            if len == 0 {
+                return Err(format!(
+                    "memory region of {} bytes leaves no private region of whole {} bytes to {} threads",
+                    args.len,
+                    args.align(),
+                    args.thread
+                )
+                .into());
+            }
+            if matches!(args.backing, Backing::File(_) | Backing::Memfd(_)) {
+                return Err("private regions are only mapped anonymously".into());
+            }
+            if args.first_touch.is_some() || !args.init_interleave.is_empty() {
+                return Err("private regions are first touched by their own threads".into());
+            }
+            if args.local_alloc && policy != Mempolicy::Default {
+                return Err(
+                    "--local-alloc binds the private regions to the nodes of the threads".into(),
+                );
+            }
+        }
+        match (&args.backing, args.regions) {
+            (_, 0) => return Err("--regions must be at least 1".into()),
+            (Backing::File(_) | Backing::Memfd(_), 2..) => {
+                return Err("separate regions are only mapped anonymously".into())
+            }
+            _ => {}
+        }
+        // populating the mapping faults the pages before the advice and policy apply
+        let populate = args.prefault && args.thp == Thp::Default && policy == Mempolicy::Default;
+        if args.private_regions && args.prefault && !populate {
+            return Err("--prefault touches the private regions from other threads".into());
+        }
+        let stripe = args.init_stripe.unwrap_or(args.page_size());
+        if !args.init_interleave.is_empty() {
+            if !init {
+                return Err(
+                    "--init-interleave touches the memory region despite --init none".into(),
+                );
+            }
+            if policy != Mempolicy::Default {
+                return Err("--init-interleave is overridden by the memory policy".into());
+            }
+            if stripe == 0 || stripe % args.page_size() != 0 {
+                return Err(format!(
+                    "stripe {stripe} is not a multiple of the page size {}",
+                    args.page_size()
+                )
+                .into());
+            }
+            if let Some(node) = args.init_interleave.iter().find(|&&node| node < 0) {
+                return Err(format!("invalid node {node}").into());
+            }
+        }
+        let align = args.align();
+        match args.align {
+            Some(align) if !align.is_power_of_two() => {
+                return Err(format!("alignment {align} is not a power of two").into())
+            }
+            Some(_) if matches!(args.backing, Backing::File(_) | Backing::Memfd(_)) => {
+                return Err("file mappings are aligned to their pages only".into())
+            }
+            _ => {}
+        }
+        if args.alloc_chunk == 0 {
+            return Err("--alloc-chunk must be at least 1 byte".into());
+        }
+        // fail early rather than with the allocation or the OOM killer, unless hugetlb pages from
+        // the pool back the memory region
+        if args.hugepage.is_none() {
+            let available = status_kb("/proc/meminfo", "MemAvailable")? * 1024;
+            match (len > available, args.allow_overcommit) {
+                (true, false) => {
+                    return Err(format!(
+                        "memory region of {len} bytes exceeds available memory of {available} \
+                         bytes, run with --allow-overcommit to spill the rest"
+                    )
+                    .into())
+                }
+                (true, true) => tracing::warn!(
+                    "overcommit: {available} of {len} bytes fit into available memory, {} spill",
+                    len - available
+                ),
+                (false, _) => {}
+            }
+        }
+        // the extra bytes mapped to align the start of the memory region
+        let pad = align - args.page_size();
+        let start = time::Instant::now();
+        let mut mem = match (&args.backing, args.hugepage) {
+            (Backing::Heap, None) if layout.count == 1 && !args.private_regions => {
+                let heap = vec![0; len + align - 1].into_boxed_slice();
+                let offset =
+                    (heap.as_ptr() as usize).next_multiple_of(align) - heap.as_ptr() as usize;
+                Memory::Heap(heap, offset..offset + len)
+            }
+            (Backing::File(path), None) => {
+                let file = std::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(path)
+                    .map_err(|e| format!("{}: {e}", path.display()))?;
+                let size = file.metadata()?.len();
+                if size != len as u64 {
+                    if size != 0 {
+                        tracing::warn!("resizing {} from {size} to {len} bytes", path.display());
+                    }
+                    file.set_len(len as _)
+                        .map_err(|e| format!("resize {}: {e}", path.display()))?;
+                }
+                // the mapping is shared, so that the workload runs over the pages of the file
+                Memory::Mmap(
+                    unsafe { memmap2::MmapOptions::new().len(len).map_mut(&file)? },
+                    0..len,
+                )
+            }
+            (Backing::Memfd(name), None) => {
+                let cname = std::ffi::CString::new(name.as_str())?;
+                let fd = unsafe {
+                    libc::memfd_create(cname.as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING)
+                };
+                if fd < 0 {
+                    return Err(
+                        format!("memfd_create {name}: {}", io::Error::last_os_error()).into(),
+                    );
+                }
+                let file = unsafe { <std::fs::File as std::os::fd::FromRawFd>::from_raw_fd(fd) };
+                file.set_len(len as _)?;
+                let seals = libc::F_SEAL_GROW | libc::F_SEAL_SHRINK;
+                if unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) } != 0 {
+                    return Err(format!("seal memfd {name}: {}", io::Error::last_os_error()).into());
+                }
+                let map = unsafe { memmap2::MmapOptions::new().len(len).map_mut(&file)? };
+                // keep the fd open for the lifetime of the process, so that other processes can
+                // map the same pages through its path
+                let fd = std::os::fd::IntoRawFd::into_raw_fd(file);
+                tracing::info!("memfd {name} at /proc/{}/fd/{fd}", process::id());
+                Memory::Mmap(map, 0..len)
+            }
+            (Backing::File(_) | Backing::Memfd(_), Some(_)) => {
+                return Err("hugetlb pages only back anonymous mappings".into())
+            }
+            (_, None) => {
+                let mut options = memmap2::MmapOptions::new();
+                if populate {
+                    options.populate();
+                }
+                // otherwise the kernel refuses a mapping beyond the memory and swap by heuristic
+                if args.allow_overcommit {
+                    options.no_reserve_swap();
+                }
+                let map = options.len(layout.count * layout.len + pad).map_anon()?;
+                let offset =
+                    (map.as_ptr() as usize).next_multiple_of(align) - map.as_ptr() as usize;
+                Memory::Mmap(map, offset..offset + len)
+            }
+            (_, Some(huge)) => {
+                let needed = (layout.count * layout.len + pad) / huge.size();
+                let (free, total) = huge.pool()?;
+                if free < needed {
+                    return Err(format!(
+                        "hugetlb pool has {free} free of {total} pages of {} bytes, {needed} needed",
+                        huge.size()
+                    )
+                    .into());
+                }
+                // hugetlb mappings are unmapped in whole pages, which the regions are made of
+                let mut options = memmap2::MmapOptions::new();
+                if populate {
+                    options.populate();
+                }
+                let map = options
+                    .len(needed * huge.size())
+                    .huge(Some(huge.bits()))
+                    .map_anon()?;
+                let offset =
+                    (map.as_ptr() as usize).next_multiple_of(align) - map.as_ptr() as usize;
+                Memory::Mmap(map, offset..offset + len)
+            }
+        };
+        // the threads owning the private regions map and initialize them
+        let owners = match args.private_regions {
+            true => Some(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(args.thread)
+                    .thread_name(|i| format!("gups-private-{i}"))
+                    .build()?,
+            ),
+            false => None,
+        };
+        let local = match args.local_alloc {
+            true => Some(local_cpus(args.thread)?),
+            false => None,
+        };
+        if let Some(owners) = &owners {
+            owners
+                .broadcast(|ctx| {
+                    let k = ctx.index();
+                    // the threads keep their CPUs to initialize the regions
+                    if let Some(local) = &local {
+                        let (cpu, _) = local[k];
+                        pin(&[cpu]).map_err(|e| format!("pin to cpu {cpu}: {e}"))?;
+                    }
+                    map_region(&mem, layout, k, args.hugepage, populate)?;
+                    if let Some(local) = &local {
+                        let (start, node) = (mem.as_ptr() as usize + k * layout.len, local[k].1);
+                        mbind(start..start + layout.len, Mempolicy::Bind, &[node])
+                            .map_err(|e| format!("mbind region {k} to node {node}: {e}"))?;
+                    }
+                    Ok(())
+                })
+                .into_iter()
+                .collect::<Result<()>>()?;
+            tracing::info!("private regions {} of {} bytes", layout.count, layout.len);
+        } else if layout.count > 1 {
+            split(&mem, layout, args.hugepage, populate)?;
+        }
+        let populated = populate && matches!(mem, Memory::Mmap(..));
+        if populated {
+            tracing::info!(
+                "prefault {len} bytes by MAP_POPULATE in {:?}",
+                start.elapsed()
+            );
+        }
+        // advise before the first touch so that the faults already take the hint
+        let advice = match args.thp {
+            Thp::Always => Some(libc::MADV_HUGEPAGE),
+            Thp::Never => Some(libc::MADV_NOHUGEPAGE),
+            Thp::Default => None,
+        };
+        if let Some(advice) = advice {
+            for map in mem_regions(&mem, args.page_size()) {
+                let (start, size) = (map.start_address(), map.size());
+                if unsafe { libc::madvise(start as _, size as _, advice) } != 0 {
+                    return Err(
+                        format!("madvise {:?}: {}", args.thp, io::Error::last_os_error()).into(),
+                    );
+                }
+            }
+        }
+        if policy != Mempolicy::Default {
+            let ranges: Vec<_> = mem_regions(&mem, args.page_size())
+                .into_iter()
+                .map(|map| {
+                    let start = map.start_address() as usize;
+                    start..start + map.size() as usize
+                })
+                .collect();
+            // the kernel silently drops offline nodes from a mask that has any online one, so try
+            // each node on its own first
+            for &node in nodes {
+                mbind(ranges[0].clone(), policy, &[node])
+                    .map_err(|e| format!("mbind {policy:?} node {node}: {e}"))?;
+            }
+            for range in ranges {
+                mbind(range, policy, nodes)
+                    .map_err(|e| format!("mbind {policy:?} nodes {nodes:?}: {e}"))?;
+            }
+        }
+        if args.prefault && !populated {
+            let start = time::Instant::now();
+            let page = args.page_size();
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(args.thread)
+                .build()?
+                .install(|| {
+                    mem.par_chunks_mut(page).for_each(|p| unsafe {
+                        // write the byte back so that prepopulated files keep their contents
+                        std::ptr::write_volatile(
+                            p.as_mut_ptr(),
+                            std::ptr::read_volatile(p.as_ptr()),
+                        )
                     })
                 });
-                count_tx.unbounded_send(indices.len()).unwrap();
+            tracing::info!("prefault {len} bytes by touching in {:?}", start.elapsed());
+        }
+        let start = time::Instant::now();
+        if !args.init_interleave.is_empty() {
+            interleave(&mut mem, &args.init_interleave, stripe, args.init)?;
+            let elapsed = start.elapsed();
+            let pages = node_pages(&mem, args.page_size())?;
+            tracing::info!(
+                "init interleave nodes {:?} stripe {stripe} in {elapsed:?} pages per node {pages:?}",
+                args.init_interleave
+            );
+        } else if let Some(owners) = owners.filter(|_| init) {
+            let parts: Vec<_> = mem.chunks_mut(layout.len).map(sync::Mutex::new).collect();
+            owners.broadcast(|ctx| {
+                let k = ctx.index();
+                args.init
+                    .fill(&mut parts[k].lock().unwrap(), k * layout.len)
             });
+            tracing::info!(
+                "init {:?} {len} bytes by {} threads in {:?}",
+                args.init,
+                args.thread,
+                start.elapsed()
+            );
+        } else if init && args.first_touch.is_none() {
+            init_chunked(args, &mut mem)?;
+            tracing::info!("init {:?} {len} bytes in {:?}", args.init, start.elapsed());
+        }
+        for (k, &(cpu, node)) in local.iter().flatten().enumerate() {
+            let part = &mem[k * layout.len..(k + 1) * layout.len];
+            match node_pages(part, args.page_size()) {
+                Ok(pages) => tracing::info!(
+                    "local alloc region {k} cpu {cpu} node {node} pages per node {pages:?}"
+                ),
+                Err(e) => tracing::warn!(
+                    "local alloc region {k} cpu {cpu} node {node} pages per node unknown: {e}"
+                ),
+            }
+        }
+        Ok(mem)
+    }
+}
+impl Memory {
+    /// Discards the pages of the memory region, which are faulted anew as zeros by the next
+    /// access. The pages of a shared file mapping are removed from the file as well, since
+    /// dropping the mapping alone would leave them in the page cache.
+    fn reset(&mut self, args: &Args) -> io::Result<time::Duration> {
+        let advice = match args.backing {
+            Backing::File(_) | Backing::Memfd(_) => libc::MADV_REMOVE,
+            Backing::Heap | Backing::Mmap => libc::MADV_DONTNEED,
+        };
+        let start = time::Instant::now();
+        let ret = unsafe { libc::madvise(self.as_mut_ptr() as _, self.len(), advice) };
+        match ret {
+            ..=-1 => Err(io::Error::last_os_error()),
+            _ => Ok(start.elapsed()),
+        }
+    }
+    /// Start of the allocation the memory region was aligned within.
+    fn raw(&self) -> *const u8 {
+        match self {
+            Memory::Heap(b, _) => b.as_ptr(),
+            Memory::Mmap(m, _) => m.as_ptr(),
+        }
+    }
+}
+impl ops::Deref for Memory {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self {
+            Memory::Heap(b, r) => &b[r.clone()],
+            Memory::Mmap(m, r) => &m[r.clone()],
+        }
+    }
+}
+impl ops::DerefMut for Memory {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            Memory::Heap(b, r) => &mut b[r.clone()],
+            Memory::Mmap(m, r) => &mut m[r.clone()],
+        }
+    }
+}
+
+/// Sizes of the updates with their weights, the smallest size being that of the elements the
+/// workloads index the memory region by.
+#[derive(Debug, Clone, serde::Serialize)]
+struct Granularity {
+    sizes: Vec<usize>,
+    weights: Vec<usize>,
+}
+
+impl Granularity {
+    fn unit(&self) -> usize {
+        *self.sizes.iter().min().unwrap()
+    }
+
+    /// The distribution of the size classes, unless there is only one.
+    fn classes(&self) -> Result<Option<rand::distributions::WeightedIndex<usize>>> {
+        match self.sizes.len() {
+            1 => Ok(None),
+            _ => Ok(Some(rand::distributions::WeightedIndex::new(
+                &self.weights,
+            )?)),
+        }
+    }
+}
+
+impl std::str::FromStr for Granularity {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (mut sizes, mut weights) = (vec![], vec![]);
+        for class in s.split(',') {
+            let (size, weight) = class.split_once(':').unwrap_or((class, "1"));
+            let err = |_| format!("size class {class} is not of the form size:weight");
+            let size = parse_size(size)?;
+            if !size.is_power_of_two() {
+                return Err(format!("size {size} is not a power of two"));
+            }
+            sizes.push(size);
+            weights.push(weight.parse().map_err(err)?);
+        }
+        // the elements are updated as a single integer, the larger sizes as words of them
+        let unit = *sizes.iter().min().unwrap();
+        if ![1, 2, 4, 8, 16].contains(&unit) {
+            return Err(format!(
+                "smallest size {unit} is not one of 1, 2, 4, 8 or 16 bytes"
+            ));
+        }
+        Ok(Self { sizes, weights })
+    }
+}
+
+/// Parses a length with an optional suffix, binary for `4K`, `2M`, `1G` or `1T` as well as
+/// `4KiB` and the like, and decimal for `4KB`, `2MB`, `1GB` or `1TB`.
+fn parse_size(s: &str) -> std::result::Result<usize, String> {
+    let (digits, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let (prefix, base) = match (unit.strip_suffix("iB"), unit.strip_suffix('i')) {
+        (Some(prefix), _) | (_, Some(prefix)) if !prefix.is_empty() => (prefix, 1024usize),
+        _ => match unit.strip_suffix('B') {
+            Some(prefix) => (prefix, 1000),
+            None => (unit, 1024),
+        },
+    };
+    let exp = match prefix {
+        "" => 0,
+        "k" | "K" => 1,
+        "m" | "M" => 2,
+        "g" | "G" => 3,
+        "t" | "T" => 4,
+        _ => return Err(format!("invalid size {s}: unknown unit {unit}")),
+    };
+    let n: usize = digits
+        .parse()
+        .map_err(|e| format!("invalid size {s}: {e}"))?;
+    n.checked_mul(base.pow(exp))
+        .ok_or_else(|| format!("size {s} overflows"))
+}
+
+/// Parses a sampling interval given as `n` or `1/n`.
+fn parse_sample(s: &str) -> std::result::Result<usize, String> {
+    match s.strip_prefix("1/").unwrap_or(s).parse() {
+        Ok(0) => Err(format!("sampling interval {s} must be at least 1")),
+        Ok(n) => Ok(n),
+        Err(e) => Err(format!("invalid sampling interval {s}: {e}")),
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+struct ChurnRange {
+    min: usize,
+    max: usize,
+}
+
+impl std::str::FromStr for ChurnRange {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (min, max) = s
+            .split_once("..")
+            .ok_or_else(|| format!("churn range {s} is not of the form min..max"))?;
+        let (min, max) = (parse_size(min)?, parse_size(max)?);
+        match min <= max {
+            true => Ok(Self { min, max }),
+            false => Err(format!("churn range {s} is empty")),
+        }
+    }
+}
+
+/// A named region of the hotset workload, `None` standing for the rest of the memory region.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String")]
+struct RegionSpec {
+    name: String,
+    size: Option<usize>,
+    weight: usize,
+}
+
+impl std::str::FromStr for RegionSpec {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || format!("region {s} is not of the form name:size:weight");
+        let mut fields = s.split(':');
+        let (Some(name), Some(size), Some(weight), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(err());
+        };
+        Ok(Self {
+            name: name.to_owned(),
+            size: match size {
+                "rest" => None,
+                size => Some(parse_size(size)?),
+            },
+            weight: weight.parse().map_err(|_| err())?,
+        })
+    }
+}
+
+impl TryFrom<String> for RegionSpec {
+    type Error = String;
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Distribution of a group of threads of the tenants workload.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+enum TenantDist {
+    Random,
+    Zipf(f64),
+    SelfSimilar(f64),
+}
+
+/// An inclusive range of threads running the same distribution.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String")]
+struct ThreadGroup {
+    threads: ops::RangeInclusive<usize>,
+    dist: TenantDist,
+}
+
+impl std::str::FromStr for ThreadGroup {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || format!("thread group {s} is not of the form first-last:workload[:param]");
+        let mut fields = s.split(':');
+        let (Some(threads), Some(name)) = (fields.next(), fields.next()) else {
+            return Err(err());
+        };
+        let threads = match threads.split_once('-') {
+            Some((first, last)) => {
+                first.parse().map_err(|_| err())?..=last.parse().map_err(|_| err())?
+            }
+            None => {
+                let t = threads.parse().map_err(|_| err())?;
+                t..=t
+            }
+        };
+        let mut param = |name| match fields.next() {
+            Some(p) => p
+                .parse::<f64>()
+                .map_err(|e| format!("thread group {s}: invalid {name} {p}: {e}")),
+            None => Err(format!("thread group {s}: missing {name}")),
+        };
+        let dist = match name {
+            "random" => TenantDist::Random,
+            "zipf" => TenantDist::Zipf(param("exponent")?),
+            "self-similar" => TenantDist::SelfSimilar(param("bias")?),
+            _ => return Err(format!("thread group {s}: unknown workload {name}")),
+        };
+        if fields.next().is_some() {
+            return Err(err());
+        }
+        Ok(Self { threads, dist })
+    }
+}
+
+impl TryFrom<String> for ThreadGroup {
+    type Error = String;
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A weighted byte range of the spans workload, `None` standing for the end of the memory region.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String")]
+struct SpanSpec {
+    start: usize,
+    end: Option<usize>,
+    weight: usize,
+}
+
+impl std::str::FromStr for SpanSpec {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || format!("span {s} is not of the form start..end:weight");
+        let (range, weight) = s.rsplit_once(':').ok_or_else(err)?;
+        let (start, end) = range.split_once("..").ok_or_else(err)?;
+        let start = parse_size(start).map_err(|e| format!("span {s}: {e}"))?;
+        let end = match end {
+            "end" => None,
+            end => Some(parse_size(end).map_err(|e| format!("span {s}: {e}"))?),
+        };
+        let weight = weight
+            .parse()
+            .map_err(|e| format!("span {s}: invalid weight {weight}: {e}"))?;
+        Ok(Self { start, end, weight })
+    }
+}
+
+impl TryFrom<String> for SpanSpec {
+    type Error = String;
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize)]
+struct WeightStep {
+    weight: usize,
+    secs: f64,
+}
+
+impl std::str::FromStr for WeightStep {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || format!("weight step {s} is not of the form weight:secs");
+        let (weight, secs) = s.split_once(':').ok_or_else(err)?;
+        let step = Self {
+            weight: weight.parse().map_err(|_| err())?,
+            secs: secs.parse().map_err(|_| err())?,
+        };
+        match step.secs > 0.0 {
+            true => Ok(step),
+            false => Err(format!("weight step {s} must last a positive time")),
+        }
+    }
+}
+
+/// A schedule file lists the phases to run, each with either a `duration` in seconds or an
+/// `update` budget (the global one if neither is given) and a workload, e.g.
+/// ```toml
+/// [[phase]]
+/// label = "hot"
+/// duration = 120
+/// workload = { hotset = { hot = [1073741824], weight = [9], reverse = true } }
+/// ```
+#[derive(Debug, serde::Deserialize)]
+struct Schedule {
+    phase: Vec<Phase>,
+}
+#[derive(Debug, serde::Deserialize)]
+struct Phase {
+    label: String,
+    duration: Option<f64>,
+    update: Option<usize>,
+    workload: Workload,
+}
+
+fn main() -> Result<()> {
+    if std::env::args().nth(1).as_deref() == Some("trace-dump") {
+        let dump = TraceDump::from_iter(std::env::args().skip(1));
+        return trace_dump(&dump.trace);
+    }
+    if std::env::args().nth(1).as_deref() == Some("heatmap-render") {
+        return heatmap_render(&HeatmapRender::from_iter(std::env::args().skip(1)));
+    }
+    // stdout is left to the summary
+    tracing_subscriber::fmt().with_writer(io::stderr).init();
+    let start = time::Instant::now();
+    let mut args = Args::from_args();
+    tracing::info!("gups args {args:?}");
+    if args.private_regions || args.local_alloc {
+        (args.private_regions, args.partitioned, args.regions) = (true, true, args.thread);
+    }
+    if args.lazy {
+        args.init = Init::None;
+    }
+    if args.prometheus.is_some() || args.verbose {
+        args.per_thread = true;
+    }
+    if !args.vmstat_fields.is_empty() {
+        args.vmstat = true;
+    }
+    // inherited by the threads spawned from here on
+    if !args.perf.is_empty() {
+        perf_open(&args.perf);
+    }
+    if args.processes != 1 {
+        processes_check(&args)?;
+        if let Backing::Heap = args.backing {
+            args.backing = Backing::Memfd("gups".into());
+        }
+    }
+    let (len, g) = (args.region_len(), args.granularity.unit());
+    args.workload.resolve_pct(len, g)?;
+    args.validate()?;
+    // removed on return after moving the process back
+    let _cgroup = Cgroup::new(&args)?;
+    if let Some(path) = &args.record_trace {
+        // each iteration appends to the trace
+        std::fs::File::create(path)?;
+    }
+    if args.per_thread {
+        let counts = (0..args.thread).map(|_| AtomicUsize::new(0)).collect();
+        let _ = THREAD_UPDATES.set(counts);
+    }
+    // the workloads of their own leave the updates per chunk uncounted
+    let counted = !matches!(
+        args.workload,
+        Workload::Chase {} | Workload::Hpcc {} | Workload::Replay { .. }
+    );
+    if args.dram_ratio.is_some() && args.processes == 1 && counted {
+        let counts = ChunkUpdates::new(args.len, args.dram_chunk, args.thread);
+        let _ = CHUNK_UPDATES.set(counts);
+    }
+    if args.check_dist {
+        if args.processes != 1 || !counted {
+            return Err("distribution check needs the workers in this process".into());
+        }
+        // in units of elements rather than bytes
+        let nelems = len / g;
+        let bucket = nelems.div_ceil(args.check_dist_buckets.min(nelems));
+        let _ = DIST_BUCKETS.set(ChunkUpdates::new(nelems, bucket, args.thread));
+    }
+    if let Some(addr) = &args.prometheus {
+        serve_metrics(addr)?;
+    }
+    if let Some(path) = &args.latency_out {
+        std::fs::write(path, "iteration,low_ns,high_ns,count\n")?;
+    }
+    if let Some(path) = &args.cdf_out {
+        if args.processes != 1 || !counted {
+            return Err("cdf needs the workers in this process".into());
+        }
+        std::fs::write(path, "iteration,chunk,updates,cdf,hottest_cdf\n")
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        let counts = ChunkUpdates::new(args.region_len(), args.cdf_chunk, args.thread);
+        let _ = CDF_UPDATES.set(counts);
+    }
+    if args.soft_dirty.is_some() {
+        soft_dirty_check()?;
+    }
+    if args.vmstat {
+        let fields = args.vmstat_fields();
+        for (field, value) in fields.iter().zip(vmstat(&fields)) {
+            if value.is_none() {
+                tracing::warn!("vmstat: no {field} in /proc/vmstat of this kernel");
+            }
+        }
+    }
+    if args.dram_ratio.unwrap_or(u64::MAX) != u64::MAX {
+        // ensure the DRAM_PFN_RANGE is initialized
+        let _ = *DRAM_PFN_RANGE;
+    }
+    if let Some(tiers) = &args.tiers {
+        let _ = TIER_RANGES.set(TierRanges::new(tiers)?);
+    }
+    if let Some(path) = &args.csv_out {
+        let _ = CSV_OUT.set(CsvOut::create(path)?);
+    }
+    if let Some(path) = &args.events_out {
+        let file = std::fs::File::create(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let _ = EVENTS.set(sync::Mutex::new(io::BufWriter::new(file)));
+    }
+    if let Some(dir) = &args.residency_dump {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    }
+    // the arguments are moved into the iterations which collect the rest
+    let json = match &args.json_out {
+        Some(path) => Some((path.clone(), Results::new(&args)?)),
+        None => None,
+    };
+    let config = config_hash(&args)?;
+    let mut mem = Memory::new(&args)?;
+    tracing::info!(
+        "memory {:?} raw {:?} length {:?} backing {:?} page size {}",
+        mem.as_ptr(),
+        mem.raw(),
+        mem.len(),
+        match (args.hugepage, args.regions) {
+            (None, 1) => args.backing.clone(),
+            _ => Backing::Mmap,
+        },
+        args.page_size()
+    );
+    tracing::info!(
+        "memory policy {:?} nodes {:?}",
+        args.mempolicy(),
+        args.membind
+    );
+    first_touch(&args, &mut mem)?;
+    setup(&args, &mut mem)?;
+    place(&args, &mem)?;
+    place_ranges(&args, &mem)?;
+    madvise(&args, &mem, "setup")?;
+    let mem = Arc::new(sync::RwLock::new(mem));
+    // unlocked on any way out, before the last reference to the memory region unmaps it
+    let _locked = mlock(&args, &mem.read().unwrap())?;
+    track_setup(&args, &mem.read().unwrap())?;
+    let handler = interrupt as *const () as libc::sighandler_t;
+    unsafe { libc::signal(libc::SIGINT, handler) };
+    if let Some(k) = fork(&args)? {
+        // the child runs its share and exits without the cleanup of the parent
+        let n = args.processes;
+        let args = Args {
+            thread: args.thread / n,
+            update: args.update / n,
+            ..args
+        };
+        let code = match async_std::task::block_on(main_loop(args, mem, &mut vec![])) {
+            Ok(()) => 0,
+            Err(e) => {
+                tracing::error!("process {k}: {e}");
+                1
+            }
+        };
+        process::exit(code);
+    }
+    let (unit, mut stats) = (args.unit, vec![]);
+    let result = async_std::task::block_on(main_loop(args, mem.clone(), &mut stats));
+    let result = reap(result);
+    comparison(&stats, unit);
+    let iterations = mem::take(&mut *RESULTS.lock().unwrap());
+    summary(
+        &iterations,
+        &stats,
+        start.elapsed(),
+        &config,
+        // an interrupt during the last iteration only cuts it short
+        result.is_err() || INTERRUPTED.load(Ordering::Relaxed),
+    )?;
+    if let Some((path, mut results)) = json {
+        results.iterations = iterations;
+        serde_json::to_writer_pretty(std::fs::File::create(&path)?, &results)?;
+        tracing::info!("results written to {}", path.display());
+    }
+    result
+}
+
+/// Prepares the memory region for the workload and logs its layout.
+fn setup(args: &Args, mem: &mut [u8]) -> Result<()> {
+    if let Some(keys) = args.keyspace {
+        let nelems = args.region_len() / args.granularity.unit();
+        let load = keys as f64 / nelems as f64;
+        // the fraction of elements expected to be hit by at least one key
+        let occupied = 1.0 - (-load).exp();
+        tracing::info!(
+            "keyspace of {keys} keys over {nelems} elements, load factor {load:.4}, occupied {occupied:.4}"
+        );
+    }
+    let hole = args.hole()?;
+    if !hole.is_empty() {
+        let nelems = args.region_len() / args.granularity.unit();
+        tracing::info!(
+            "hole bytes {:?} leaves {} of {nelems} elements accessible",
+            hole.start * args.granularity.unit()..hole.end * args.granularity.unit(),
+            nelems - hole.len()
+        );
+    }
+    match &args.workload {
+        Workload::Hotset {
+            regions, reverse, ..
+        } if !regions.is_empty() => {
+            let (len, g) = (args.region_len(), args.granularity.unit());
+            // offset of the memory region within the mapping the dram portion report covers
+            let base = mem.as_ptr() as usize
+                - mem_regions(mem, args.page_size())[0].start_address() as usize;
+            let top = len / g * g;
+            for (name, r, weight) in regions_layout(len, g, regions)? {
+                let r = if *reverse {
+                    top - r.end..top - r.start
+                } else {
+                    r
+                };
+                let chunks =
+                    (base + r.start) / args.dram_chunk..=(base + r.end - 1) / args.dram_chunk;
+                tracing::info!(
+                    "region {name} bytes {r:?} weight {weight} dram portion chunks {chunks:?}"
+                );
+            }
+        }
+        Workload::Hotset {
+            hot,
+            weight,
+            offsets,
+            reverse,
+            ..
+        } => {
+            let (len, g) = (args.region_len(), args.granularity.unit());
+            let hots = hotset_layout(len, g, hot, weight, offsets)?;
+            for (i, (r, weight)) in hots.into_iter().enumerate() {
+                let top = len / g * g;
+                let r = if *reverse {
+                    top - r.end..top - r.start
+                } else {
+                    r
+                };
+                tracing::info!("hot region {i} bytes {r:?} weight {weight}");
+            }
+        }
+        Workload::Normal {
+            mean_frac,
+            stddev_frac,
+            reverse,
+        } => {
+            let top = args.region_len() / args.granularity.unit() * args.granularity.unit();
+            for k in [1.0, 2.0] {
+                let (lo, hi) = (mean_frac - k * stddev_frac, mean_frac + k * stddev_frac);
+                let to_byte = |f: f64| (f.clamp(0.0, 1.0) * top as f64) as usize;
+                let r = match reverse {
+                    true => top - to_byte(hi)..top - to_byte(lo),
+                    false => to_byte(lo)..to_byte(hi),
+                };
+                tracing::info!("hotspot {k}-sigma bytes {r:?}");
+            }
+        }
+        Workload::Spans { spans } => {
+            let layout = spans_layout(args.region_len(), args.granularity.unit(), spans)?;
+            let total: usize = layout.iter().map(|(_, w)| w).sum();
+            for (r, weight) in layout {
+                let p = weight as f64 / total as f64;
+                tracing::info!("span bytes {r:?} weight {weight} probability {p:.4}");
+            }
+        }
+        Workload::Chase {} => chase_init(mem, args.granularity.unit(), args.seed)?,
+        Workload::Hpcc {} => hpcc_init(mem, args.granularity.unit())?,
+        Workload::Replay { trace, op, wrap } => {
+            let trace = Trace::open(trace, *op)?;
+            let nelems = (args.region_len() / args.granularity.unit()) as u64;
+            let (records, outside) = trace.check(args.thread, nelems)?;
+            if outside > 0 && !wrap {
+                tracing::info!("dropping {outside} of {records} records out of range");
+            } else {
+                tracing::info!("trace of {records} records, {outside} wrapped around");
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Discards the pages of the memory region before an iteration by `--reset-between-iterations`.
+fn reset(args: &Args, mem: &sync::RwLock<Memory>) -> Result<()> {
+    if !args.reset_between_iterations || is_child() {
+        return Ok(());
+    }
+    let mut mem = mem.write().unwrap();
+    let elapsed = mem
+        .reset(args)
+        .map_err(|e| format!("reset memory region: {e}"))?;
+    tracing::info!("reset {} bytes in {elapsed:?}", mem.len());
+    Ok(())
+}
+
+/// Runs the iterations, adding the figures of each to `stats` as it ends.
+async fn main_loop(
+    args: Args,
+    mem: Arc<sync::RwLock<Memory>>,
+    stats: &mut Vec<IterationStats>,
+) -> Result<()> {
+    if let Workload::Schedule { file } = &args.workload {
+        let schedule: Schedule = toml::from_str(&std::fs::read_to_string(file)?)?;
+        for (i, phase) in schedule.phase.into_iter().enumerate() {
+            if i > 0 {
+                reset(&args, &mem)?;
+            }
+            let mut args = args.clone();
+            if let (Some(_), None, Workload::Hpcc {}) =
+                (phase.duration, phase.update, &phase.workload)
+            {
+                return Err(format!("hpcc phase {} needs an update budget", phase.label).into());
+            }
+            args.update = match (phase.update, phase.duration) {
+                (Some(update), _) => update,
+                (None, Some(_)) => usize::MAX,
+                (None, None) => args.update,
+            };
+            args.workload = phase.workload;
+            let (len, g) = (args.region_len(), args.granularity.unit());
+            args.workload.resolve_pct(len, g)?;
+            if let Workload::Zipf { exponent_sweep, .. } = &args.workload {
+                if !exponent_sweep.is_empty() {
+                    return Err(format!("zipf phase {} cannot sweep", phase.label).into());
+                }
+            }
+            setup(&args, &mut mem.write().unwrap())?;
+            tracing::info!("phase {} start", phase.label);
+            let duration = phase.duration.map(time::Duration::from_secs_f64);
+            stats.push(iteration(&phase.label, args, mem.clone(), duration).await?);
+        }
+        return Ok(());
+    }
+    // warm-up
+    tracing::info!("warm up iteration start");
+    // the pages are faulted by the first access unless touched before
+    let first = match args.init == Init::None && !args.prefault {
+        true => "first with faults",
+        false => "first",
+    };
+    stats.push(iteration(first, args.clone(), mem.clone(), None).await?);
+    // second
+    reset(&args, &mem)?;
+    tracing::info!("second iteration start");
+    stats.push(iteration("warm up", args.clone(), mem.clone(), None).await?);
+    if args.madvise_after_warmup && !is_child() {
+        madvise(&args, &mem.read().unwrap(), "warm up")?;
+    }
+    if let Workload::Zipf { exponent_sweep, .. } = &args.workload {
+        if !exponent_sweep.is_empty() {
+            for &e in exponent_sweep {
+                let mut args = args.clone();
+                if let Workload::Zipf { exponent, .. } = &mut args.workload {
+                    *exponent = e;
+                }
+                reset(&args, &mem)?;
+                tracing::info!("exponent {e} iteration start");
+                stats.push(iteration(&format!("exponent {e}"), args, mem.clone(), None).await?);
+            }
+            return Ok(());
+        }
+    }
+    // final
+    reset(&args, &mem)?;
+    tracing::info!("third iteration start");
+    stats.push(iteration("last", args, mem.clone(), None).await?);
+
+    Ok(())
+}
+
+/// Runs the workload once, stopping early after `duration` if given, and returns its figures
+/// for the comparison at exit, those of a child left at none.
+async fn iteration(
+    label: &str,
+    args: Args,
+    mem: Arc<sync::RwLock<Memory>>,
+    duration: Option<time::Duration>,
+) -> Result<IterationStats> {
+    let seq = ITERATIONS.fetch_add(1, Ordering::Relaxed) + 1;
+    let group = GROUP.get();
+    if let Some(group @ Group { child: Some(_), .. }) = group {
+        group.iteration(seq, args, mem).await?;
+        return Ok(IterationStats {
+            label: label.to_owned(),
+            ..Default::default()
+        });
+    }
+    if INTERRUPTED.load(Ordering::Relaxed) {
+        return Err("interrupted".into());
+    }
+    let (count_tx, count_rx) = mpsc::unbounded();
+    let regions = mem_regions(&mem.read().unwrap(), args.page_size());
+    let region = mem_span(&regions);
+    if let Ok(hole) = args
+        .hole()
+        .map(|h| h.start * args.granularity.unit()..h.end * args.granularity.unit())
+    {
+        if !hole.is_empty() && args.dram_ratio.is_some() {
+            // in units of the chunks of the dram portion report
+            let base = mem.read().unwrap().as_ptr() as usize - region.start_address() as usize;
+            let chunks =
+                (base + hole.start) / args.dram_chunk..=(base + hole.end - 1) / args.dram_chunk;
+            tracing::info!("iteration {label} dram portion chunks {chunks:?} overlap the hole");
+        }
+    }
+    let logical = match args.dram_ratio.or(args.idle_track) {
+        Some(_) => {
+            let mem = mem.read().unwrap();
+            logical_pages(&args, mem.as_ptr() as usize, mem.len())?
+        }
+        None => None,
+    };
+    let config = ReportConfig::new(&args, duration)?;
+    if let Some(churn) = config.churn {
+        LIVE.store(args.region_len() / churn.granularity, Ordering::Relaxed);
+    }
+    if let Some(growth) = config.growth {
+        HOT_LEN.store(growth.initial, Ordering::Relaxed);
+    }
+    let (unit, page_size) = (config.unit, config.page_size);
+    let cgroup = CGROUP.get().map(|path| cgroup_stat(path)).transpose()?;
+    let perf = PERF.get().map(|counters| {
+        let threads = status_count("Threads").unwrap_or(0);
+        (
+            perf_read(counters),
+            UPDATES.load(Ordering::Relaxed),
+            threads,
+        )
+    });
+    // the faults and present pages before the iteration
+    let lazy = match args.lazy {
+        true => Some((rusage()?, present_pages(&regions, page_size)?)),
+        false => None,
+    };
+    FLIPPED.store(false, Ordering::Relaxed);
+    let track = match args.track_protect {
+        Some(ms) => {
+            tracing::warn!("track: iteration {label} accesses tracked every {ms} ms by mprotect perturb the workload");
+            track_protect(libc::PROT_NONE)?;
+            let actor = async_std::task::spawn(track_actor(
+                label.to_owned(),
+                time::Duration::from_millis(ms),
+                args.track_dir.clone(),
+            ));
+            Some((actor, args.track_dir.clone()))
+        }
+        None => None,
+    };
+    let weights = match &args.workload {
+        Workload::Hotset {
+            weight_schedule, ..
+        } if !weight_schedule.is_empty() => {
+            HOT_WEIGHT.store(weight_schedule[0].weight, Ordering::Relaxed);
+            Some(async_std::task::spawn(weight_schedule_actor(
+                label.to_owned(),
+                weight_schedule.clone(),
+            )))
+        }
+        _ => None,
+    };
+    // unless interrupted since the check above
+    STOP.store(INTERRUPTED.load(Ordering::Relaxed), Ordering::Relaxed);
+    let timer = duration.map(|d| {
+        async_std::task::spawn(async move {
+            async_std::task::sleep(d).await;
+            STOP.store(true, Ordering::Relaxed);
+        })
+    });
+    let relay = label.to_owned();
+    let (res, stats) = join!(
+        async_std::task::spawn_blocking(move || match group {
+            Some(group) => group.relay(&relay, seq, unit, count_tx),
+            None => gups_worker(args, mem, count_tx),
+        }),
+        reporting_actor(label, count_rx, config, regions.clone(), logical)
+    );
+    if let Some(timer) = timer {
+        timer.cancel().await;
+    }
+    if let Some(weights) = weights {
+        weights.cancel().await;
+    }
+    if let Some((actor, dir)) = track {
+        actor.cancel().await;
+        track_dump(label, &dir, false)?;
+    }
+    match anon_huge_pages(&regions) {
+        Ok(kb) => tracing::info!("iteration {label} anon huge pages {kb} kB"),
+        Err(e) => tracing::warn!("iteration {label} anon huge pages unknown: {e}"),
+    }
+    log_smaps(label, &regions);
+    if let (Some(path), Some(before)) = (CGROUP.get(), cgroup) {
+        let current = std::fs::read_to_string(path.join("memory.current"))?;
+        let stat = cgroup_stat(path)?;
+        // the sizes as they are and the event counters over the iteration
+        let fields: Vec<_> = CGROUP_STAT
+            .iter()
+            .filter_map(|&name| {
+                let value = stat.get(name)?;
+                let value = match name.starts_with("pg") {
+                    true => value - before.get(name).copied().unwrap_or(0),
+                    false => *value,
+                };
+                Some(format!("{name} {value}"))
+            })
+            .collect();
+        tracing::info!(
+            "iteration {label} cgroup memory.current {} {}",
+            current.trim(),
+            fields.join(" ")
+        );
+    }
+    if let (Some(counters), Some((before, updates, threads))) = (PERF.get(), perf) {
+        // the counts of the workers are only added to ours once they exit
+        let deadline = time::Instant::now() + time::Duration::from_millis(100);
+        while status_count("Threads").is_ok_and(|n| n > threads) && time::Instant::now() < deadline
+        {
+            std::thread::sleep(time::Duration::from_millis(1));
+        }
+        let updates = UPDATES.load(Ordering::Relaxed) - updates;
+        let counts: Vec<_> = perf_read(counters)
+            .into_iter()
+            .zip(before)
+            .map(|(now, before)| now - before)
+            .collect();
+        let totals: String = counters
+            .iter()
+            .zip(&counts)
+            .map(|((event, _), n)| format!(" {} {n}", event.name))
+            .collect();
+        let rates: String = counters
+            .iter()
+            .zip(&counts)
+            .map(|((event, _), &n)| format!(" {} {:.3}", event.name, n as f64 / updates as f64))
+            .collect();
+        let count = |name| {
+            let k = counters.iter().position(|(e, _)| e.name == name)?;
+            Some(counts[k] as f64)
+        };
+        let ipc = match (count("instructions"), count("cycles")) {
+            (Some(i), Some(c)) => format!(" ipc {:.3}", i / c),
+            _ => String::new(),
+        };
+        tracing::info!("perf: iteration {label}{totals} per update{rates}{ipc}");
+    }
+    if let Some((usage, present)) = lazy {
+        let now = rusage()?;
+        let (minor, major) = (
+            now.ru_minflt - usage.ru_minflt,
+            now.ru_majflt - usage.ru_majflt,
+        );
+        let pages = regions.iter().map(|r| r.size() as usize).sum::<usize>() / page_size;
+        let now = present_pages(&regions, page_size)?;
+        tracing::info!(
+            "iteration {label} faults minor {minor} major {major} pages present {now} of {pages} newly {}",
+            now as i64 - present as i64
+        );
+    }
+    res.map(|()| stats)
+}
+
+/// A hardware or software event counted by `perf_event_open`.
+#[derive(Debug, Clone, serde::Serialize)]
+struct PerfEvent {
+    name: String,
+    kind: u32,
+    config: u64,
+}
+impl std::str::FromStr for PerfEvent {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        // the generalized cache events are of the cache, the op and the result
+        let cache = |cache: u64, miss: bool| (3, cache | (miss as u64) << 16);
+        let (kind, config) = match s {
+            "cycles" => (0, 0),
+            "instructions" => (0, 1),
+            "cache-references" => (0, 2),
+            "cache-misses" => (0, 3),
+            "branch-misses" => (0, 5),
+            "page-faults" => (1, 2),
+            "LLC-loads" => cache(2, false),
+            "LLC-load-misses" => cache(2, true),
+            "dTLB-loads" => cache(3, false),
+            "dTLB-load-misses" => cache(3, true),
+            "node-loads" => cache(6, false),
+            "node-load-misses" => cache(6, true),
+            _ => match s.strip_prefix('r').map(|hex| u64::from_str_radix(hex, 16)) {
+                Some(Ok(config)) => (4, config),
+                _ => return Err(format!("unknown perf event {s}")),
+            },
+        };
+        Ok(Self {
+            name: s.to_owned(),
+            kind,
+            config,
+        })
+    }
+}
+
+/// The `perf_event_attr` of the kernel up to `sig_data`.
+#[repr(C)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    rest: [u64; 10],
+}
+
+/// The counters of `--perf` opened before any other thread.
+static PERF: sync::OnceLock<Vec<(PerfEvent, std::fs::File)>> = sync::OnceLock::new();
+
+/// Opens the counters of the events for this thread and those it spawns, leaving out with a
+/// warning those the kernel refuses, such as due to `perf_event_paranoid`.
+fn perf_open(events: &[PerfEvent]) {
+    use std::os::fd::FromRawFd;
+    let mut counters = vec![];
+    for event in events {
+        let attr = PerfEventAttr {
+            kind: event.kind,
+            size: mem::size_of::<PerfEventAttr>() as u32,
+            config: event.config,
+            sample_period: 0,
+            sample_type: 0,
+            read_format: 0,
+            // inherit and exclude_hv
+            flags: 1 << 1 | 1 << 6,
+            rest: [0; 10],
+        };
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const PerfEventAttr,
+                0,
+                -1,
+                -1,
+                0,
+            )
+        };
+        match fd {
+            ..=-1 => {
+                let paranoid = std::fs::read_to_string("/proc/sys/kernel/perf_event_paranoid");
+                let paranoid = paranoid.as_deref().unwrap_or("unknown").trim();
+                let e = io::Error::last_os_error();
+                tracing::warn!(
+                    "perf: {} not counted: {e}, perf_event_paranoid {paranoid}",
+                    event.name
+                );
+            }
+            fd => counters.push((event.clone(), unsafe {
+                std::fs::File::from_raw_fd(fd as _)
+            })),
+        }
+    }
+    if !counters.is_empty() {
+        let _ = PERF.set(counters);
+    }
+}
+
+/// The counts of the counters so far.
+fn perf_read(counters: &[(PerfEvent, std::fs::File)]) -> Vec<u64> {
+    counters
+        .iter()
+        .map(|(_, file)| {
+            let (mut file, mut count) = (file, [0; 8]);
+            match file.read_exact(&mut count) {
+                Ok(()) => u64::from_ne_bytes(count),
+                Err(_) => 0,
+            }
+        })
+        .collect()
+}
+
+/// The count of the given field of `/proc/self/status`, such as `Threads`.
+fn status_count(field: &str) -> Result<usize> {
+    let status = std::fs::read_to_string("/proc/self/status")?;
+    Ok(status
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+        .and_then(|n| n.trim().parse().ok())
+        .ok_or_else(|| format!("no {field} in /proc/self/status"))?)
+}
+
+/// The resource usage of the process.
+fn rusage() -> io::Result<libc::rusage> {
+    getrusage(libc::RUSAGE_SELF)
+}
+
+/// The resource usage of the calling thread alone.
+fn thread_rusage() -> io::Result<libc::rusage> {
+    getrusage(libc::RUSAGE_THREAD)
+}
+
+fn getrusage(who: libc::c_int) -> io::Result<libc::rusage> {
+    let mut usage = unsafe { mem::zeroed() };
+    match unsafe { libc::getrusage(who, &mut usage) } {
+        ..=-1 => Err(io::Error::last_os_error()),
+        _ => Ok(usage),
+    }
+}
+
+/// Number of the pages of `page_size` bytes of the regions that are present.
+fn present_pages(regions: &[pagemap::MemoryRegion], page_size: usize) -> Result<usize> {
+    let mut pagemap = pagemap::PageMap::new(process::id() as _)?;
+    let mut present = 0;
+    for region in regions {
+        let ptes = pagemap.pagemap_region(region)?;
+        present += ptes
+            .iter()
+            .step_by(page_size / *PAGE_SIZE)
+            .filter(|e| e.present())
+            .count();
+    }
+    Ok(present)
+}
+
+pub struct Mod<T, U, X: Copy>
+where
+    T: Distribution<U>,
+    U: ops::Rem<X, Output = U>,
+{
+    distribution: T,
+    len: X,
+    _marker: marker::PhantomData<U>,
+}
+impl<T, U, X: Copy> Mod<T, U, X>
+where
+    T: Distribution<U>,
+    U: ops::Rem<X, Output = U>,
+{
+    pub fn new(distribution: T, len: X) -> Self {
+        Self {
+            distribution,
+            len,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+impl<T, U, X: Copy> Distribution<U> for Mod<T, U, X>
+where
+    T: Distribution<U>,
+    U: ops::Rem<X, Output = U>,
+{
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> U {
+        self.distribution.sample(rng) % self.len
+    }
+}
+
+/// Folds a continuous distribution onto `[0, len)` by re-sampling draws that fall outside of it,
+/// i.e. the result follows the distribution truncated to the range rather than piling up the
+/// excess probability on the boundary elements as clamping would.
+pub struct Truncated<T: Distribution<f64>> {
+    distribution: T,
+    len: usize,
+}
+impl<T: Distribution<f64>> Truncated<T> {
+    pub fn new(distribution: T, len: usize) -> Self {
+        Self { distribution, len }
+    }
+}
+impl<T: Distribution<f64>> Distribution<usize> for Truncated<T> {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        loop {
+            let x = self.distribution.sample(rng);
+            if x >= 0.0 && x < self.len as f64 {
+                return x as usize;
+            }
+        }
+    }
+}
+
+/// A seeded pseudo-random permutation of `[0, len)` without a table: a balanced Feistel network
+/// over the smallest power of four covering `len`, cycle-walking the values landing outside.
+pub struct Scrambled<T: Distribution<usize>> {
+    distribution: T,
+    len: usize,
+    half_bits: u32,
+    keys: [u64; 4],
+}
+impl<T: Distribution<usize>> Scrambled<T> {
+    pub fn new(distribution: T, len: usize, seed: u64) -> Self {
+        let bits = usize::BITS - len.saturating_sub(1).leading_zeros();
+        let mut keys = [0; 4];
+        for (i, k) in keys.iter_mut().enumerate() {
+            *k = splitmix64(seed.wrapping_add(i as u64));
+        }
+        Self {
+            distribution,
+            len,
+            half_bits: bits.div_ceil(2).max(1),
+            keys,
+        }
+    }
+    pub fn permute(&self, mut x: usize) -> usize {
+        let mask = (1 << self.half_bits) - 1;
+        loop {
+            let (mut l, mut r) = (x >> self.half_bits, x & mask);
+            for k in self.keys {
+                (l, r) = (r, l ^ (splitmix64(r as u64 ^ k) as usize & mask));
+            }
+            x = l << self.half_bits | r;
+            if x < self.len {
+                return x;
+            }
+        }
+    }
+}
+impl<T: Distribution<usize>> Distribution<usize> for Scrambled<T> {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        self.permute(self.distribution.sample(rng))
+    }
+}
+
+/// Offset in elements by which the hot regions have been slid forward so far.
+static HOT_SHIFT: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of elements the workload is folded into, which churns over time.
+static LIVE: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Periodic change of the accessible part performed by the reporting actor, in elements.
+#[derive(Debug, Clone, Copy)]
+struct Churn {
+    interval: time::Duration,
+    min: usize,
+    max: usize,
+    granularity: usize,
+}
+
+/// Periodic slide of the hot regions performed by the reporting actor.
+#[derive(Debug, Clone, Copy)]
+struct HotShift {
+    interval: time::Duration,
+    step: usize,
+    len: usize,
+    granularity: usize,
+}
+
+/// Picks a page from a distribution over the page numbers and then a uniform element within it.
+pub struct Paged<T: Distribution<usize>> {
+    pages: T,
+    per_page: usize,
+}
+impl<T: Distribution<usize>> Paged<T> {
+    pub fn new(pages: T, per_page: usize) -> Self {
+        Self { pages, per_page }
+    }
+}
+impl<T: Distribution<usize>> Distribution<usize> for Paged<T> {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        self.pages.sample(rng) * self.per_page + rng.gen_range(0..self.per_page)
+    }
+}
+
+/// Samples the distribution of the group of the executing thread.
+pub struct Tenants {
+    /// The distribution of each thread
+    dists: Vec<sync::Arc<TenantSampler>>,
+}
+enum TenantSampler {
+    Random(Uniform<usize>),
+    Zipf(ZipfDistribution),
+    SelfSimilar(SelfSimilar),
+}
+impl Tenants {
+    fn new(len: usize, thread: usize, groups: &[ThreadGroup]) -> Result<Self> {
+        let mut dists = vec![None; thread];
+        for ThreadGroup { threads, dist } in groups {
+            let sampler = sync::Arc::new(match *dist {
+                TenantDist::Random => TenantSampler::Random(Uniform::new(0, len)),
+                TenantDist::Zipf(exponent) => TenantSampler::Zipf(
+                    ZipfDistribution::new(len, exponent)
+                        .map_err(|()| format!("invalid zipf exponent {exponent}"))?,
+                ),
+                TenantDist::SelfSimilar(bias) if (0.5..1.0).contains(&bias) => {
+                    TenantSampler::SelfSimilar(SelfSimilar::new(len, bias))
+                }
+                TenantDist::SelfSimilar(bias) => {
+                    return Err(format!("self-similar bias {bias} out of range [0.5, 1)").into())
+                }
+            });
+            for t in threads.clone() {
+                match dists.get_mut(t) {
+                    Some(d @ None) => *d = Some(sampler.clone()),
+                    Some(Some(_)) => return Err(format!("thread {t} is in several groups").into()),
+                    None => {
+                        return Err(format!("thread {t} out of range of {thread} threads").into())
+                    }
+                }
+            }
+        }
+        let dists = dists
+            .into_iter()
+            .enumerate()
+            .map(|(t, d)| d.ok_or_else(|| format!("thread {t} is in no group")))
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self { dists })
+    }
+}
+impl Pattern for Tenants {
+    fn index<R: rand::Rng + ?Sized>(&self, rng: &mut R, _i: usize) -> usize {
+        match &*self.dists[rayon::current_thread_index().unwrap()] {
+            TenantSampler::Random(d) => d.sample(rng),
+            TenantSampler::Zipf(d) => d.sample(rng) - 1,
+            TenantSampler::SelfSimilar(d) => d.sample(rng),
+        }
+    }
+}
+
+/// The b-model over `[0, len)`: halves the range until a single element is left, keeping the lower
+/// half with probability `bias`, so that the skew is the same at every scale.
+pub struct SelfSimilar {
+    len: usize,
+    bias: f64,
+}
+impl SelfSimilar {
+    pub fn new(len: usize, bias: f64) -> Self {
+        Self { len, bias }
+    }
+}
+impl Distribution<usize> for SelfSimilar {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let (mut lo, mut hi) = (0, self.len);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if rng.gen_bool(self.bias) {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        lo
+    }
+}
+
+/// Skips the elements of a hole by shifting the samples at or past its start by its length, so the
+/// distribution covers `len - hole.len()` elements and each sample costs the same.
+pub struct Holed<T: Distribution<usize>> {
+    distribution: T,
+    hole: ops::Range<usize>,
+}
+impl<T: Distribution<usize>> Holed<T> {
+    pub fn new(distribution: T, hole: ops::Range<usize>) -> Self {
+        Self { distribution, hole }
+    }
+}
+impl<T: Distribution<usize>> Distribution<usize> for Holed<T> {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let x = self.distribution.sample(rng);
+        if x >= self.hole.start {
+            x + self.hole.len()
+        } else {
+            x
+        }
+    }
+}
+
+/// Weight of each hot region, updated over time by the weight schedule.
+static HOT_WEIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Samples the hot regions with probability `scale * weight / (scale * weight + 1)` and the cold
+/// ones otherwise, or only the side given if the other is missing.
+pub struct HotCold<'a, H: Distribution<usize>, C: Distribution<usize>> {
+    hot: Option<H>,
+    cold: Option<C>,
+    weight: &'a AtomicUsize,
+    scale: usize,
+}
+impl<'a, H: Distribution<usize>, C: Distribution<usize>> HotCold<'a, H, C> {
+    pub fn new(hot: Option<H>, cold: Option<C>, weight: &'a AtomicUsize, scale: usize) -> Self {
+        Self {
+            hot,
+            cold,
+            weight,
+            scale,
+        }
+    }
+}
+impl<'a, H: Distribution<usize>, C: Distribution<usize>> Distribution<usize> for HotCold<'a, H, C> {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        match (&self.hot, &self.cold) {
+            (Some(hot), Some(cold)) => {
+                let w = self.weight.load(Ordering::Relaxed) * self.scale;
+                if rng.gen_range(0..=w) < w {
+                    hot.sample(rng)
+                } else {
+                    cold.sample(rng)
+                }
+            }
+            (Some(hot), None) => hot.sample(rng),
+            (None, Some(cold)) => cold.sample(rng),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+/// Length in elements of the growing or shrinking hot region.
+static HOT_LEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Steady growth of the hot region performed by the reporting actor, in elements per second.
+#[derive(Debug, Clone, Copy)]
+struct HotGrowth {
+    initial: usize,
+    max: usize,
+    rate: f64,
+    granularity: usize,
+}
+
+impl HotGrowth {
+    fn interval(&self) -> time::Duration {
+        time::Duration::from_millis(100)
+    }
+}
+
+/// Samples the hot region of `HOT_LEN` elements starting at `start`.
+pub struct GrowingHot {
+    start: usize,
+}
+impl Distribution<usize> for GrowingHot {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        self.start + rng.gen_range(0..HOT_LEN.load(Ordering::Relaxed))
+    }
+}
+
+/// Samples the elements of `[0, end)` outside of the `GrowingHot` region, or inside it once the
+/// region has grown to cover everything.
+pub struct GrowingCold {
+    start: usize,
+    end: usize,
+}
+impl Distribution<usize> for GrowingCold {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let hot = HOT_LEN.load(Ordering::Relaxed);
+        if hot >= self.end {
+            return self.start + rng.gen_range(0..hot);
+        }
+        let x = rng.gen_range(0..self.end - hot);
+        if x >= self.start {
+            x + hot
+        } else {
+            x
+        }
+    }
+}
+
+/// Adds the current value of a shared offset to each sample, wrapping is left to an outer `Mod`.
+pub struct Shifted<'a, T: Distribution<usize>> {
+    distribution: T,
+    offset: &'a AtomicUsize,
+}
+impl<'a, T: Distribution<usize>> Shifted<'a, T> {
+    pub fn new(distribution: T, offset: &'a AtomicUsize) -> Self {
+        Self {
+            distribution,
+            offset,
+        }
+    }
+}
+impl<'a, T: Distribution<usize>> Distribution<usize> for Shifted<'a, T> {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        self.distribution.sample(rng) + self.offset.load(Ordering::Relaxed)
+    }
+}
+
+/// Maps the `i`-th update of the iteration space to the index of the element to update.
+pub trait Pattern {
+    fn index<R: rand::Rng + ?Sized>(&self, rng: &mut R, i: usize) -> usize;
+}
+impl<D: Distribution<usize>> Pattern for D {
+    fn index<R: rand::Rng + ?Sized>(&self, rng: &mut R, _i: usize) -> usize {
+        self.sample(rng)
+    }
+}
+
+/// The elements recently accessed by a thread for `Latest`.
+#[derive(Default)]
+struct Recent {
+    ring: Vec<usize>,
+    next: usize,
+}
+
+/// The draws of a thread for `Latest` and how many of those hit the window, only written by the
+/// thread itself and alone on its cache line.
+#[derive(Default)]
+#[repr(align(64))]
+struct Rereferenced {
+    draws: AtomicUsize,
+    hits: AtomicUsize,
+}
+
+thread_local! {
+    static RECENT: RefCell<Recent> = RefCell::default();
+}
+
+/// Draws the recency rank of the element to access from a zipf distribution over the window,
+/// re-accessing the element of that rank if the window of the thread holds that many yet and
+/// falling back to a uniform draw otherwise.
+pub struct Latest {
+    recent: ZipfDistribution,
+    uniform: Uniform<usize>,
+    window: usize,
+    /// The accounting of each thread
+    rereferenced: Vec<Rereferenced>,
+}
+impl Latest {
+    pub fn new(len: usize, window: usize, exponent: f64, thread: usize) -> Result<Self> {
+        if window < 1 {
+            return Err("latest window must hold at least 1 element".into());
+        }
+        let recent = ZipfDistribution::new(window, exponent)
+            .map_err(|()| format!("invalid latest exponent {exponent}"))?;
+        Ok(Self {
+            recent,
+            uniform: Uniform::new(0, len),
+            window,
+            rereferenced: (0..thread).map(|_| Rereferenced::default()).collect(),
+        })
+    }
+    /// Fraction of the draws of all threads that re-accessed a recent element, zero without
+    /// draws, and the number of draws.
+    pub fn rereferenced(&self) -> (f64, usize) {
+        let sum = |f: fn(&Rereferenced) -> &AtomicUsize| {
+            let counts = self
+                .rereferenced
+                .iter()
+                .map(|r| f(r).load(Ordering::Relaxed));
+            counts.sum::<usize>()
+        };
+        let (draws, hits) = (sum(|r| &r.draws), sum(|r| &r.hits));
+        match draws {
+            0 => (0.0, 0),
+            _ => (hits as f64 / draws as f64, draws),
+        }
+    }
+}
+impl Pattern for Latest {
+    fn index<R: rand::Rng + ?Sized>(&self, rng: &mut R, _i: usize) -> usize {
+        RECENT.with(|recent| {
+            let recent = &mut *recent.borrow_mut();
+            // rank 1 is the latest element
+            let k = self.recent.sample(rng) - 1;
+            let hit = k < recent.ring.len();
+            let index = if hit {
+                recent.ring[(recent.next + self.window - 1 - k) % self.window]
+            } else {
+                self.uniform.sample(rng)
+            };
+            if recent.ring.len() < self.window {
+                recent.ring.push(index);
+            } else {
+                recent.ring[recent.next] = index;
+            }
+            recent.next = (recent.next + 1) % self.window;
+            // the counters of the thread, so a plain increment rather than a locked one
+            let t = rayon::current_thread_index().unwrap_or_default();
+            let counts = &self.rereferenced[t];
+            let add =
+                |c: &AtomicUsize, n| c.store(c.load(Ordering::Relaxed) + n, Ordering::Relaxed);
+            add(&counts.draws, 1);
+            add(&counts.hits, hit as usize);
+            index
+        })
+    }
+}
+
+/// The position of a thread on its walk, and the pages it has visited.
+struct Walker {
+    rng: rand::rngs::StdRng,
+    cur: usize,
+    pages: Vec<u64>,
+}
+
+thread_local! {
+    static WALKER: RefCell<Option<Walker>> = const { RefCell::new(None) };
+}
+
+/// Moves each thread by a uniform offset in `[-step, step]` from its previous element, wrapping
+/// around at the ends. The walks are seeded by `seed` plus the thread index and start at a random
+/// element.
+pub struct Walk {
+    len: usize,
+    step: usize,
+    granularity: usize,
+    seed: u64,
+    visited: Vec<AtomicUsize>,
+}
+impl Walk {
+    pub fn new(len: usize, step: usize, granularity: usize, seed: u64, thread: usize) -> Self {
+        Self {
+            len,
+            step,
+            granularity,
+            seed,
+            visited: (0..thread).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+    /// Number of distinct pages each thread visited.
+    pub fn visited(self) -> Vec<usize> {
+        self.visited
+            .into_iter()
+            .map(AtomicUsize::into_inner)
+            .collect()
+    }
+}
+impl Pattern for Walk {
+    fn index<R: rand::Rng + ?Sized>(&self, _rng: &mut R, _i: usize) -> usize {
+        let t = rayon::current_thread_index().unwrap_or_default();
+        WALKER.with(|walker| {
+            let mut walker = walker.borrow_mut();
+            let walker = walker.get_or_insert_with(|| {
+                let mut rng = rand::SeedableRng::seed_from_u64(self.seed.wrapping_add(t as u64));
+                let cur = rand::Rng::gen_range(&mut rng, 0..self.len);
+                let pages = self.len * self.granularity / *PAGE_SIZE + 1;
+                Walker {
+                    rng,
+                    cur,
+                    pages: vec![0; pages.div_ceil(64)],
+                }
+            });
+            let delta = walker.rng.gen_range(0..=2 * self.step);
+            walker.cur =
+                (walker.cur + delta % self.len + self.len - self.step % self.len) % self.len;
+            let page = walker.cur * self.granularity / *PAGE_SIZE;
+            let (word, bit) = (page / 64, 1 << (page % 64));
+            if walker.pages[word] & bit == 0 {
+                walker.pages[word] |= bit;
+                self.visited[t].fetch_add(1, Ordering::Relaxed);
+            }
+            walker.cur
+        })
+    }
+}
+
+/// Generation of the latest `Cluster`, telling the cluster of a thread left from an earlier one.
+static CLUSTER_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The generation of the `Cluster`, the first element of the current cluster of the thread and
+    /// the updates left to it
+    static CLUSTER: Cell<(usize, usize, usize)> = const { Cell::new((0, 0, 0)) };
+}
+
+/// Issues `burst` uniform updates within a cluster of `size` elements before picking the next
+/// cluster, starting at a random one of the `starts` pages that leave room for a whole cluster.
+/// A cluster longer than a page spans the pages following its first.
+pub struct Cluster {
+    size: usize,
+    burst: usize,
+    starts: usize,
+    page: usize,
+    generation: usize,
+}
+impl Cluster {
+    pub fn new(len: usize, size: usize, burst: usize, granularity: usize) -> Self {
+        let page = *PAGE_SIZE / granularity;
+        Self {
+            size,
+            burst,
+            starts: (len - size) / page + 1,
+            page,
+            generation: CLUSTER_GENERATION.fetch_add(1, Ordering::Relaxed) + 1,
+        }
+    }
+}
+impl Pattern for Cluster {
+    fn index<R: rand::Rng + ?Sized>(&self, rng: &mut R, _i: usize) -> usize {
+        let (generation, mut start, mut left) = CLUSTER.with(Cell::get);
+        // a cluster left from an earlier instance is given up
+        if left == 0 || generation != self.generation {
+            (start, left) = (rng.gen_range(0..self.starts) * self.page, self.burst);
+        }
+        CLUSTER.with(|c| c.set((self.generation, start, left - 1)));
+        start + rng.gen_range(0..self.size)
+    }
+}
+
+/// The current segment of a thread on its chain of the `Markov` of the given generation.
+struct MarkovState {
+    generation: usize,
+    rng: rand::rngs::StdRng,
+    segment: usize,
+}
+
+/// Generation of the latest `Markov`, telling the chains of a thread left from an earlier one.
+static MARKOV_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static MARKOV: RefCell<Option<MarkovState>> = const { RefCell::new(None) };
+}
+
+/// Moves each thread from segment to segment of `[0, len)` by the rows of a transition matrix,
+/// accessing a uniform element of each. The chains are seeded by `seed` plus the thread index and
+/// start at a random segment.
+pub struct Markov {
+    rows: Vec<rand::distributions::WeightedIndex<f64>>,
+    segment_len: usize,
+    seed: u64,
+    generation: usize,
+    /// The visits of each thread per segment, only written by the thread itself
+    visits: Vec<Box<[AtomicUsize]>>,
+}
+impl Markov {
+    pub fn new(len: usize, matrix: &[Vec<f64>], seed: u64, thread: usize) -> Result<Self> {
+        let k = matrix.len();
+        if k == 0 || k > len {
+            return Err(format!("{k} segments out of range of {len} elements").into());
+        }
+        let mut rows = vec![];
+        for (i, row) in matrix.iter().enumerate() {
+            let sum: f64 = row.iter().sum();
+            if row.len() != k || (sum - 1.0).abs() > 1e-3 {
+                return Err(format!("row {i} {row:?} of the matrix is not stochastic").into());
+            }
+            rows.push(rand::distributions::WeightedIndex::new(row)?);
+        }
+        Ok(Self {
+            rows,
+            segment_len: len / k,
+            seed,
+            generation: MARKOV_GENERATION.fetch_add(1, Ordering::Relaxed) + 1,
+            visits: (0..thread)
+                .map(|_| (0..k).map(|_| AtomicUsize::new(0)).collect())
+                .collect(),
+        })
+    }
+    /// Reads the matrix from a CSV or TOML file.
+    pub fn load(path: &path::Path) -> Result<Vec<Vec<f64>>> {
+        let text = std::fs::read_to_string(path)?;
+        if matches!(path.extension(), Some(ext) if ext == "csv") {
+            let rows = text.lines().filter(|line| !line.trim().is_empty());
+            rows.map(|line| line.split(',').map(|p| Ok(p.trim().parse()?)).collect())
+                .collect()
+        } else {
+            #[derive(serde::Deserialize)]
+            struct File {
+                matrix: Vec<Vec<f64>>,
+            }
+            Ok(toml::from_str::<File>(&text)?.matrix)
+        }
+    }
+    /// Fraction of the accesses of all threads that went to each segment, none without accesses.
+    pub fn stationary(&self) -> Vec<f64> {
+        let visits: Vec<usize> = (0..self.rows.len())
+            .map(|k| {
+                let visits = self.visits.iter().map(|v| v[k].load(Ordering::Relaxed));
+                visits.sum()
+            })
+            .collect();
+        let total: usize = visits.iter().sum();
+        if total == 0 {
+            return vec![];
+        }
+        visits.iter().map(|&v| v as f64 / total as f64).collect()
+    }
+}
+impl Pattern for Markov {
+    fn index<R: rand::Rng + ?Sized>(&self, _rng: &mut R, _i: usize) -> usize {
+        MARKOV.with(|state| {
+            let mut state = state.borrow_mut();
+            let t = rayon::current_thread_index().unwrap_or_default();
+            let current = matches!(&*state, Some(s) if s.generation == self.generation);
+            if !current {
+                let mut rng: rand::rngs::StdRng =
+                    rand::SeedableRng::seed_from_u64(self.seed.wrapping_add(t as u64));
+                *state = Some(MarkovState {
+                    generation: self.generation,
+                    segment: rng.gen_range(0..self.rows.len()),
+                    rng,
+                });
+            }
+            let state = state.as_mut().unwrap();
+            state.segment = self.rows[state.segment].sample(&mut state.rng);
+            // the counter of the thread, so a plain increment rather than a locked one
+            let visits = &self.visits[t][state.segment];
+            visits.store(visits.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+            state.segment * self.segment_len + state.rng.gen_range(0..self.segment_len)
+        })
+    }
+}
+
+pub struct Sequential {
+    len: usize,
+    reverse: bool,
+}
+impl Sequential {
+    pub fn new(len: usize, reverse: bool) -> Self {
+        Self { len, reverse }
+    }
+}
+impl Pattern for Sequential {
+    fn index<R: rand::Rng + ?Sized>(&self, _rng: &mut R, i: usize) -> usize {
+        let i = i % self.len;
+        if self.reverse {
+            self.len - 1 - i
+        } else {
+            i
+        }
+    }
+}
+
+/// Visits `offset, offset + stride, offset + 2 * stride, ...` modulo `len`. When `stride` is not
+/// coprime with `len` the sequence cycles after `len / gcd(stride, len)` steps, at which point the
+/// starting element is shifted by one so that the other residue classes get their turn.
+pub struct Strided {
+    len: usize,
+    stride: usize,
+    offset: usize,
+    cycle: usize,
+}
+impl Strided {
+    pub fn new(len: usize, stride: usize, offset: usize) -> Self {
+        let stride = stride % len;
+        Self {
+            len,
+            stride,
+            offset,
+            cycle: len / gcd(stride, len),
+        }
+    }
+}
+impl Pattern for Strided {
+    fn index<R: rand::Rng + ?Sized>(&self, _rng: &mut R, i: usize) -> usize {
+        let (pass, step) = (i / self.cycle, i % self.cycle);
+        let shift = (self.offset + pass) % self.len;
+        ((step as u128 * self.stride as u128 + shift as u128) % self.len as u128) as usize
+    }
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+pub struct Backwards<T, U, M>
+where
+    T: Distribution<U>,
+    M: ops::Sub<U, Output = U> + Copy,
+{
+    distribution: T,
+    minuend: M,
+    _marker: marker::PhantomData<U>,
+}
+impl<T, U, M> Backwards<T, U, M>
+where
+    T: Distribution<U>,
+    M: ops::Sub<U, Output = U> + Copy,
+{
+    pub fn new(distribution: T, minuend: M) -> Self {
+        Self {
+            distribution,
+            minuend,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+impl<T, U, M> Distribution<U> for Backwards<T, U, M>
+where
+    T: Distribution<U>,
+    M: ops::Sub<U, Output = U> + Copy,
+{
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> U {
+        self.minuend - self.distribution.sample(rng)
+    }
+}
+
+fn gups_worker(
+    args: Args,
+    mem: Arc<sync::RwLock<Memory>>,
+    count: mpsc::UnboundedSender<usize>,
+) -> Result<()> {
+    let (updates, thread, len, g) = (
+        args.update,
+        args.thread,
+        args.region_len(),
+        args.granularity.unit(),
+    );
+    let end = len / g;
+    let mem = &mut **mem.write().unwrap();
+    if args.read_ratio > 100 {
+        return Err(format!("read ratio {}% out of range", args.read_ratio).into());
+    }
+    match (args.scan_every, args.scan_bytes) {
+        (Some(0), _) => return Err("scan interval must be at least 1 update".into()),
+        // the passes read the whole mapped memory region regardless of the parts
+        (Some(_), Some(bytes)) if bytes > mem.len() => {
+            let len = mem.len();
+            return Err(format!("scan length {bytes} exceeds memory region of {len} bytes").into());
+        }
+        _ => {}
+    }
+    let hole = args.hole()?;
+    if !hole.is_empty()
+        && !matches!(
+            args.workload,
+            Workload::Random { .. } | Workload::Zipf { .. }
+        )
+    {
+        return Err(format!("{:?} does not support holes", args.workload).into());
+    }
+    if args.granularity.sizes.len() > 1
+        && matches!(
+            args.workload,
+            Workload::Chase {} | Workload::Hpcc {} | Workload::Replay { .. }
+        )
+    {
+        return Err(format!("{:?} does not support mixed granularity", args.workload).into());
+    }
+    if let Some(size) = args.granularity.sizes.iter().find(|&&size| size > len) {
+        return Err(format!("update size {size} exceeds memory region of {len} bytes").into());
+    }
+    if let Some(ChurnRange { min, max }) = args.churn_range {
+        if min < g || max > len {
+            return Err(format!(
+                "churn range {min}..{max} out of range of granularity {g} and memory region of {len} bytes"
+            )
+            .into());
+        }
+        if matches!(
+            args.workload,
+            Workload::Chase {} | Workload::Hpcc {} | Workload::Replay { .. }
+        ) {
+            return Err(format!("{:?} does not support churn", args.workload).into());
+        }
+    }
+    if !(0.0..=1.0).contains(&args.repeat_prob) {
+        return Err(format!("repeat probability {} out of range", args.repeat_prob).into());
+    }
+    if args.record_sample == 0 {
+        return Err("record sample must be at least 1".into());
+    }
+    if args.op == Op::Copy {
+        if args.copy_size == 0 || args.copy_size > len {
+            return Err(format!(
+                "copy size {} out of range of memory region of {len} bytes",
+                args.copy_size
+            )
+            .into());
+        }
+        if args.granularity.sizes.len() > 1 {
+            return Err("copy op does not support mixed granularity".into());
+        }
+        if matches!(args.workload, Workload::Replay { .. }) {
+            return Err(format!("{:?} does not support the copy op", args.workload).into());
+        }
+    }
+    if args.run_length == 0 || args.run_length > end {
+        return Err(format!(
+            "run length {} out of range of {end} elements",
+            args.run_length
+        )
+        .into());
+    }
+    if args.run_length > 1
+        && matches!(
+            args.workload,
+            Workload::Chase {}
+                | Workload::Hpcc {}
+                | Workload::Replay { .. }
+                | Workload::Stencil { .. }
+        )
+    {
+        return Err(format!("{:?} does not support runs", args.workload).into());
+    }
+    if let Workload::Hotset {
+        flip_at: Some(f), ..
+    } = args.workload
+    {
+        if !(0.0..1.0).contains(&f) {
+            return Err(format!("flip fraction {f} out of range [0, 1)").into());
+        }
+    }
+    if let Some(rate) = args.arrival_rate {
+        if !(rate > 0.0 && rate.is_finite()) {
+            return Err(format!("arrival rate {rate} must be positive").into());
+        }
+        if matches!(
+            args.workload,
+            Workload::Chase {} | Workload::Hpcc {} | Workload::Replay { .. }
+        ) {
+            return Err(format!("{:?} does not support pacing", args.workload).into());
+        }
+    }
+    if args.op != Op::Add && matches!(args.workload, Workload::Chase {} | Workload::Hpcc {}) {
+        return Err(format!("{:?} does not support the {:?} op", args.workload, args.op).into());
+    }
+    if args.partitioned
+        && matches!(
+            args.workload,
+            Workload::Chase {} | Workload::Hpcc {} | Workload::Replay { .. }
+        )
+    {
+        return Err(format!("{:?} does not support partitioning", args.workload).into());
+    }
+    if let Some(keys) = args.keyspace {
+        if keys == 0 {
+            return Err("keyspace must hold at least a key".into());
+        }
+        if !hole.is_empty()
+            || matches!(
+                args.workload,
+                Workload::Chase {} | Workload::Hpcc {} | Workload::Replay { .. }
+            )
+        {
+            return Err(format!("{:?} does not support a keyspace", args.workload).into());
+        }
+    }
+    // the workloads draw keys which are hashed onto the elements
+    let (len, end) = match args.keyspace {
+        Some(keys) => (keys * g, keys),
+        None => (len, end),
+    };
+    match args.workload.clone() {
+        Workload::Hotset {
+            regions,
+            reverse: r,
+            write_region,
+            weight_schedule,
+            hot_grow,
+            hot_shrink,
+            ..
+        } if !regions.is_empty() => {
+            if hot_grow.or(hot_shrink).is_some() || !weight_schedule.is_empty() {
+                return Err("named regions can neither grow nor follow a weight schedule".into());
+            }
+            if !matches!(write_region, WriteRegion::Both) {
+                return Err("named regions cannot restrict the write region".into());
+            }
+            let layout = regions_layout(len, g, &regions)?;
+            let d = || {
+                let v = layout
+                    .iter()
+                    .map(|(_, r, _)| Uniform::new(r.start / g, r.end / g));
+                let w = layout.iter().map(|&(_, _, weight)| weight as f64);
+                Mix::new(v, w)
+            };
+            hotset_do(&args, mem, d()?, d()?, r, None, count)?;
+        }
+        Workload::Hotset {
+            hot,
+            weight,
+            offsets,
+            reverse: r,
+            write_region,
+            weight_schedule,
+            hot_grow,
+            hot_shrink,
+            ..
+        } => {
+            let hots = hotset_layout(len, g, &hot, &weight, &offsets)?;
+            let growing = hot_grow.or(hot_shrink).is_some();
+            let scheduled = !weight_schedule.is_empty();
+            // the cold gaps in between share weight 1 in proportion to their lengths
+            let (mut hot_v, mut hot_w, mut colds) = (vec![], vec![], vec![]);
+            let mut cursor = 0;
+            for (range, weight) in &hots {
+                if cursor < range.start / g {
+                    colds.push(cursor..range.start / g);
+                }
+                hot_v.push(Uniform::new(range.start / g, range.end / g));
+                // the scheduled weight applies to all hot regions alike
+                hot_w.push(if scheduled { 1.0 } else { *weight as f64 });
+                cursor = range.end / g;
+            }
+            if cursor < end {
+                colds.push(cursor..end);
+            }
+            let cold: usize = colds.iter().map(|r| r.len()).sum();
+            let cold_v: Vec<_> = colds.iter().map(|r| Uniform::new(r.start, r.end)).collect();
+            let cold_w: Vec<_> = colds.iter().map(|r| r.len() as f64 / cold as f64).collect();
+            let hot_d = || Mix::new(hot_v.clone(), hot_w.clone());
+            let cold_d = || match cold_v.is_empty() {
+                true => Ok(None),
+                false => Mix::new(cold_v.clone(), cold_w.clone()).map(Some),
+            };
+            let total = AtomicUsize::new(hots.iter().map(|(_, weight)| weight).sum());
+            let (weight, scale) = match scheduled {
+                true => (&HOT_WEIGHT, hots.len()),
+                false => (&total, 1),
+            };
+            // undo the reversal and the shift to find out whether an index landed in a hot region
+            let hots: Vec<_> = hots
+                .iter()
+                .map(|(range, _)| range.start / g..range.end / g)
+                .collect();
+            let is_hot = |i: usize| {
+                let i = if r { end - 1 - i } else { i };
+                let i = (i + end - HOT_SHIFT.load(Ordering::Relaxed)) % end;
+                if growing {
+                    return (hots[0].start..hots[0].start + HOT_LEN.load(Ordering::Relaxed))
+                        .contains(&i);
+                }
+                let k = hots.partition_point(|range| range.end <= i);
+                k < hots.len() && hots[k].contains(&i)
+            };
+            // the bytes per hot and cold region are accounted only for restricted writes, sparing
+            // the default the classification of each access
+            let is_hot: Option<&(dyn Fn(usize) -> bool + Sync)> = match write_region {
+                WriteRegion::Both => None,
+                _ => Some(&is_hot),
+            };
+            if growing {
+                let hot_d = || GrowingHot {
+                    start: hots[0].start,
+                };
+                let cold_d = || GrowingCold {
+                    start: hots[0].start,
+                    end,
+                };
+                let reads = HotCold::new(Some(hot_d()), Some(cold_d()), weight, scale);
+                let writes = match write_region {
+                    WriteRegion::Both => HotCold::new(Some(hot_d()), Some(cold_d()), weight, scale),
+                    WriteRegion::Hot => HotCold::new(Some(hot_d()), None, weight, scale),
+                    WriteRegion::Cold => HotCold::new(None, Some(cold_d()), weight, scale),
+                };
+                hotset_do(&args, mem, reads, writes, r, is_hot, count)?;
+            } else {
+                let reads = HotCold::new(Some(hot_d()?), cold_d()?, weight, scale);
+                let writes = match write_region {
+                    WriteRegion::Both => HotCold::new(Some(hot_d()?), cold_d()?, weight, scale),
+                    WriteRegion::Hot => HotCold::new(Some(hot_d()?), None, weight, scale),
+                    WriteRegion::Cold if cold_v.is_empty() => {
+                        return Err("no cold region left to write to".into())
+                    }
+                    WriteRegion::Cold => HotCold::new(None, cold_d()?, weight, scale),
+                };
+                hotset_do(&args, mem, reads, writes, r, is_hot, count)?;
+            }
+        }
+        Workload::Zipf {
+            exponent,
+            reverse: r,
+            scramble,
+            ..
+        } => {
+            let nelems = len / g - hole.len();
+            // the ranks start at 1
+            let d = ZipfDistribution::new(nelems, exponent)
+                .unwrap()
+                .map(|rank| rank - 1);
+            if scramble {
+                let d = Scrambled::new(d, nelems, args.seed);
+                gups_do(&args, mem, Holed::new(d, hole), count)?;
+            } else if r {
+                let d = Backwards::new(d, nelems - 1);
+                gups_do(&args, mem, Holed::new(d, hole), count)?;
+            } else {
+                gups_do(&args, mem, Holed::new(d, hole), count)?;
+            }
+        }
+        Workload::Random { reverse: r } => {
+            let nelems = end - hole.len();
+            let d = Uniform::new(0, nelems);
+            if r {
+                let d = Backwards::new(d, nelems - 1);
+                gups_do(&args, mem, Holed::new(d, hole), count)?;
+            } else {
+                gups_do(&args, mem, Holed::new(d, hole), count)?;
+            }
+        }
+        Workload::Sequential { reverse: r } => {
+            let done = gups_do(&args, mem, Sequential::new(end, r), count)?;
+            tracing::info!(
+                "sequential scan completed {} full passes over {end} elements",
+                done / end
+            );
+        }
+        Workload::Strided { stride, offset } => {
+            if stride < 1 {
+                return Err(format!("stride {stride} must be at least 1").into());
+            }
+            if offset >= end {
+                return Err(format!("offset {offset} out of range of {end} elements").into());
+            }
+            let d = Strided::new(end, stride, offset);
+            gups_do(&args, mem, d, count)?;
+        }
+        Workload::Normal {
+            mean_frac,
+            stddev_frac,
+            reverse: r,
+        } => {
+            if !(0.0..=1.0).contains(&mean_frac) {
+                return Err(format!("mean fraction {mean_frac} out of range [0, 1]").into());
+            }
+            if stddev_frac <= 0.0 {
+                return Err(
+                    format!("standard deviation fraction {stddev_frac} must be positive").into(),
+                );
+            }
+            let nelems = len / g;
+            let normal = Normal::new(mean_frac * nelems as f64, stddev_frac * nelems as f64)?;
+            let d = Truncated::new(normal, nelems);
+            if r {
+                gups_do(&args, mem, Backwards::new(d, nelems - 1), count)?;
+            } else {
+                gups_do(&args, mem, d, count)?;
+            }
+        }
+        Workload::Exp { lambda, reverse: r } => {
+            if lambda <= 0.0 {
+                return Err(format!("exponential rate {lambda} must be positive").into());
+            }
+            let nelems = len / g;
+            let exp = Exp::new(lambda)?.map(move |x| x * nelems as f64);
+            let d = Truncated::new(exp, nelems);
+            if r {
+                gups_do(&args, mem, Backwards::new(d, nelems - 1), count)?;
+            } else {
+                gups_do(&args, mem, d, count)?;
+            }
+        }
+        Workload::Pareto { shape, reverse: r } => {
+            if shape <= 0.0 {
+                return Err(format!("pareto shape {shape} must be positive").into());
+            }
+            let nelems = len / g;
+            // move the support [1, inf) of the unit scale pareto onto the first element
+            let pareto = Pareto::new(1.0, shape)?.map(|x| x - 1.0);
+            let d = Truncated::new(pareto, nelems);
+            if r {
+                gups_do(&args, mem, Backwards::new(d, nelems - 1), count)?;
+            } else {
+                gups_do(&args, mem, d, count)?;
+            }
+        }
+        Workload::Latest { exponent, window } => {
+            let d = Latest::new(len / g, window, exponent, thread)?;
+            gups_do_with(&args, mem, &d, &d, None, count)?;
+            let (fraction, draws) = d.rereferenced();
+            tracing::info!("latest re-referenced {fraction:.4} of {draws} draws");
+        }
+        Workload::PageZipf {
+            exponent,
+            reverse: r,
+        } => {
+            let npages = len / *PAGE_SIZE;
+            if npages == 0 || g > *PAGE_SIZE {
+                return Err(format!("page zipf needs at least a page of {len} bytes").into());
+            }
+            let pages = ZipfDistribution::new(npages, exponent)
+                .map_err(|()| format!("invalid page zipf exponent {exponent}"))?
+                .map(|rank| rank - 1);
+            let per_page = *PAGE_SIZE / g;
+            if r {
+                let pages = Backwards::new(pages, npages - 1);
+                gups_do(&args, mem, Paged::new(pages, per_page), count)?;
+            } else {
+                gups_do(&args, mem, Paged::new(pages, per_page), count)?;
+            }
+        }
+        Workload::Tenants {
+            per_thread_workload,
+        } => {
+            let d = Tenants::new(end, args.thread, &per_thread_workload)?;
+            gups_do(&args, mem, d, count)?;
+        }
+        Workload::Stencil { radius } => {
+            if 2 * radius + 1 > end {
+                return Err(
+                    format!("stencil radius {radius} out of range of {end} elements").into(),
+                );
+            }
+            gups_do(&args, mem, Uniform::new(0, end), count)?;
+        }
+        Workload::SelfSimilar { bias, reverse: r } => {
+            if !(0.5..1.0).contains(&bias) {
+                return Err(format!("self-similar bias {bias} out of range [0.5, 1)").into());
+            }
+            let d = SelfSimilar::new(end, bias);
+            if r {
+                gups_do(&args, mem, Backwards::new(d, end - 1), count)?;
+            } else {
+                gups_do(&args, mem, d, count)?;
+            }
+        }
+        Workload::Markov { matrix } => {
+            let d = Markov::new(end, &Markov::load(&matrix)?, args.seed, thread)?;
+            gups_do_with(&args, mem, &d, &d, None, count)?;
+            let stationary: Vec<_> = d
+                .stationary()
+                .into_iter()
+                .map(|p| format!("{p:.4}"))
+                .collect();
+            match stationary.is_empty() {
+                true => tracing::info!("markov segment distribution unknown without draws"),
+                false => {
+                    tracing::info!("markov segment distribution [{}]", stationary.join(", "))
+                }
+            }
+        }
+        Workload::Spans { spans } => {
+            let layout = spans_layout(len, g, &spans)?;
+            let v = layout
+                .iter()
+                .map(|(r, _)| Uniform::new(r.start / g, r.end / g));
+            let w = layout.iter().map(|&(_, weight)| weight as f64);
+            gups_do(&args, mem, Mix::new(v, w)?, count)?;
+        }
+        Workload::Cluster {
+            cluster_size,
+            burst,
+        } => {
+            if cluster_size < g || cluster_size > len {
+                return Err(format!(
+                    "cluster size {cluster_size} out of range of granularity {g} and memory region of {len} bytes"
+                )
+                .into());
+            }
+            if cluster_size % g != 0 {
+                return Err(format!(
+                    "cluster size {cluster_size} is not a multiple of granularity {g}"
+                )
+                .into());
+            }
+            if burst < 1 {
+                return Err("cluster burst must be at least 1 update".into());
+            }
+            let d = Cluster::new(end, cluster_size / g, burst, g);
+            gups_do(&args, mem, d, count)?;
+        }
+        Workload::Walk { step } => {
+            let d = Walk::new(end, step, g, args.seed, thread);
+            gups_do_with(&args, mem, &d, &d, None, count)?;
+            for (t, pages) in d.visited().into_iter().enumerate() {
+                tracing::info!("walk thread {t} visited {pages} distinct pages");
+            }
+        }
+        Workload::Replay { trace, op, wrap } => {
+            let trace = Trace::open(&trace, op)?;
+            let records = replay_do(&trace, thread, g, wrap, args.op, mem, count)?;
+            tracing::info!("replayed {records} records");
+        }
+        Workload::Schedule { .. } => {
+            return Err("schedules cannot be nested".into());
+        }
+        Workload::Hpcc {} => {
+            let table = hpcc_table(mem);
+            hpcc_do(updates, thread, table, count)?;
+            let errors = hpcc_verify(updates, thread, table)?;
+            let frac = errors as f64 / table.len() as f64;
+            tracing::info!(
+                "hpcc verification found {errors} of {} entries in error ({:.4}%) {}",
+                table.len(),
+                frac * 100.0,
+                if frac <= 0.01 { "passed" } else { "failed" }
+            );
+        }
+        Workload::Chase {} => {
+            // still sending the counts, the reporting actor sees the latency before its end
+            let per_thread = chase_do(updates / thread, thread, g, mem, &count)?;
+            let hops: usize = per_thread.iter().map(|&(n, _)| n).sum();
+            // over the threads that hopped at all, as one stopped early may not have
+            let ns: u128 = per_thread
+                .iter()
+                .filter(|&&(n, _)| n > 0)
+                .map(|&(_, elapsed)| elapsed.as_nanos())
+                .sum();
+            let ns = (hops > 0).then(|| ns as f64 / hops as f64);
+            match ns {
+                Some(ns) => tracing::info!(
+                    "pointer chase {} hops per thread average {ns:.1} ns per hop",
+                    hops / thread
+                ),
+                None => tracing::info!("pointer chase made no hops"),
+            }
+            *HOP_NS.lock().unwrap() = ns;
+        }
+    }
+    Ok(())
+}
+
+/// Runs the hotset workload with its distributions slid forward by the hot shift and reversed if
+/// asked to.
+fn hotset_do<D: Distribution<usize> + Sync, W: Distribution<usize> + Sync>(
+    args: &Args,
+    mem: &mut [u8],
+    reads: D,
+    writes: W,
+    reverse: bool,
+    is_hot: Option<&(dyn Fn(usize) -> bool + Sync)>,
+    count: mpsc::UnboundedSender<usize>,
+) -> Result<usize> {
+    let end = args.region_len() / args.granularity.unit();
+    let (reads, writes) = (
+        Mod::new(Shifted::new(reads, &HOT_SHIFT), end),
+        Mod::new(Shifted::new(writes, &HOT_SHIFT), end),
+    );
+    if reverse {
+        let (reads, writes) = (
+            Backwards::new(reads, end - 1),
+            Backwards::new(writes, end - 1),
+        );
+        gups_do_with(args, mem, &reads, &writes, is_hot, count)
+    } else {
+        gups_do_with(args, mem, &reads, &writes, is_hot, count)
+    }
+}
+
+/// Byte ranges of the named regions laid out back to back from the start of the memory region,
+/// along with their names and weights.
+fn regions_layout(
+    len: usize,
+    granularity: usize,
+    regions: &[RegionSpec],
+) -> Result<Vec<(String, ops::Range<usize>, usize)>> {
+    let sized: usize = regions.iter().filter_map(|r| r.size).sum();
+    if regions.iter().filter(|r| r.size.is_none()).count() > 1 {
+        return Err("only a single region can take the rest".into());
+    }
+    if sized > len {
+        return Err(format!("regions of {sized} bytes exceed memory region of {len} bytes").into());
+    }
+    let mut cursor = 0;
+    let mut layout = vec![];
+    for RegionSpec { name, size, weight } in regions {
+        let size = size.unwrap_or(len - sized) / granularity * granularity;
+        if size == 0 {
+            return Err(format!("region {name} holds no element").into());
+        }
+        layout.push((name.clone(), cursor..cursor + size, *weight));
+        cursor += size;
+    }
+    Ok(layout)
+}
+
+/// Byte ranges of the spans paired with their weights and sorted by their start, checked to be
+/// non-empty, disjoint, aligned to `granularity` and within the memory region.
+fn spans_layout(
+    len: usize,
+    granularity: usize,
+    spans: &[SpanSpec],
+) -> Result<Vec<(ops::Range<usize>, usize)>> {
+    let mut layout = vec![];
+    for &SpanSpec { start, end, weight } in spans {
+        let end = end.unwrap_or(len / granularity * granularity);
+        let name = format!("{start}..{end}:{weight}");
+        if start >= end || end > len {
+            return Err(format!("span {name} out of range of memory region of {len} bytes").into());
+        }
+        if start % granularity != 0 || end % granularity != 0 {
+            return Err(format!("span {name} not aligned to granularity {granularity}").into());
+        }
+        if weight == 0 {
+            return Err(format!("span {name} has no weight").into());
+        }
+        layout.push((start..end, weight));
+    }
+    layout.sort_by_key(|(r, _)| r.start);
+    for w in layout.windows(2) {
+        if w[0].0.end > w[1].0.start {
+            return Err(format!("spans {:?} and {:?} overlap", w[0].0, w[1].0).into());
+        }
+    }
+    Ok(layout)
+}
+
+/// Byte ranges of the hot regions paired with their weights and sorted by their start, either at
+/// the given `offsets` or evenly spaced over the memory region.
+fn hotset_layout(
+    len: usize,
+    granularity: usize,
+    hot: &[usize],
+    weight: &[usize],
+    offsets: &[usize],
+) -> Result<Vec<(ops::Range<usize>, usize)>> {
+    if weight.len() != hot.len() {
+        return Err(format!(
+            "{} weights given for {} hot regions",
+            weight.len(),
+            hot.len()
+        )
+        .into());
+    }
+    if !offsets.is_empty() && offsets.len() != hot.len() {
+        return Err(format!(
+            "{} offsets given for {} hot regions",
+            offsets.len(),
+            hot.len()
+        )
+        .into());
+    }
+    let mut hots: Vec<_> = hot
+        .iter()
+        .zip(weight)
+        .enumerate()
+        .map(|(i, (&size, &weight))| {
+            let start = match offsets.get(i) {
+                Some(&offset) if offset + size > len => Err(format!(
+                    "hot region of {size} bytes at {offset} exceeds memory region of {len} bytes"
+                )),
+                Some(&offset) => Ok(offset),
+                None => Ok(i * len / hot.len()),
+            }?;
+            let start = start / granularity * granularity;
+            Ok((start..start + size / granularity * granularity, weight))
+        })
+        .collect::<Result<_>>()?;
+    hots.sort_by_key(|(r, _)| r.start);
+    for (i, (r, _)) in hots.iter().enumerate() {
+        if r.is_empty() || r.end > len {
+            return Err(
+                format!("hot region {r:?} out of range of memory region of {len} bytes").into(),
+            );
+        }
+        if i > 0 && hots[i - 1].0.end > r.start {
+            return Err(format!("hot regions {:?} and {r:?} overlap", hots[i - 1].0).into());
+        }
+    }
+    Ok(hots)
+}
+
+thread_local! {
+    static MEM: RefCell<&'static mut [u8]> = RefCell::default();
+    /// Source of the copies performed by this thread
+    static COPY_SOURCE: RefCell<Vec<u8>> = RefCell::default();
+    /// Arrivals of the updates of this thread if paced
+    static PACER: RefCell<Option<Pacer>> = const { RefCell::new(None) };
+    /// Updates performed by this thread since its last sequential read pass
+    static SINCE_SCAN: Cell<usize> = const { Cell::new(0) };
+    /// Updates performed by this thread since its last timed one
+    static SINCE_SAMPLE: Cell<usize> = const { Cell::new(0) };
+    /// Updates per chunk of the current batch of this thread for `ChunkUpdates`
+    static CHUNK_BATCH: RefCell<Vec<usize>> = const { RefCell::new(vec![]) };
+    /// Bytes written per size class and latencies sampled in the current chunk of this thread,
+    /// kept to spare an allocation per chunk
+    static CHUNK_SCRATCH: RefCell<(Vec<usize>, Vec<u64>)> = const { RefCell::new((vec![], vec![])) };
+}
+/// Open-loop arrivals of the updates of a thread, with exponentially distributed gaps.
+struct Pacer {
+    next: time::Instant,
+    gap: Exp<f64>,
+    rng: rand::rngs::StdRng,
+}
+impl Pacer {
+    fn new(rate: f64, seed: u64) -> Self {
+        Self {
+            next: time::Instant::now(),
+            gap: Exp::new(rate).unwrap(),
+            rng: rand::SeedableRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Waits for the arrival of the next update, sleeping for the bulk of long gaps and spinning
+    /// for the rest. Falling behind issues the late updates right away.
+    fn wait(&mut self) {
+        let now = time::Instant::now();
+        if self.next > now {
+            let left = self.next - now;
+            if left > time::Duration::from_micros(200) {
+                std::thread::sleep(left - time::Duration::from_micros(100));
+            }
+            while time::Instant::now() < self.next {
+                std::hint::spin_loop();
+            }
+        }
+        self.next += time::Duration::from_secs_f64(self.gap.sample(&mut self.rng));
+    }
+}
+
+/// The clock timing the sampled updates, counting either ns since an epoch or TSC ticks of the
+/// given ns.
+#[derive(Debug, Clone, Copy)]
+enum Clock {
+    Instant(time::Instant),
+    Tsc(f64),
+}
+impl Clock {
+    fn now(&self) -> u64 {
+        match self {
+            Clock::Instant(epoch) => epoch.elapsed().as_nanos() as u64,
+            Clock::Tsc(_) => rdtsc(),
+        }
+    }
+
+    /// The ns passed since `start` taken by `now`.
+    fn since(&self, start: u64) -> u64 {
+        let ticks = self.now().saturating_sub(start);
+        match self {
+            Clock::Instant(_) => ticks,
+            Clock::Tsc(ns) => (ticks as f64 * ns) as u64,
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn rdtsc() -> u64 {
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+#[cfg(not(target_arch = "x86_64"))]
+fn rdtsc() -> u64 {
+    0
+}
+
+static TSC_NS: sync::OnceLock<f64> = sync::OnceLock::new();
+
+/// The ns per TSC tick, calibrated against the monotonic clock on first use.
+fn tsc_ns() -> Result<f64> {
+    if !cfg!(target_arch = "x86_64") {
+        return Err("timing by the TSC needs x86_64".into());
+    }
+    if let Some(&ns) = TSC_NS.get() {
+        return Ok(ns);
+    }
+    let (start, ticks) = (time::Instant::now(), rdtsc());
+    std::thread::sleep(time::Duration::from_millis(50));
+    let ns = start.elapsed().as_nanos() as f64 / rdtsc().wrapping_sub(ticks) as f64;
+    tracing::info!("tsc calibrated at {:.3} GHz", 1.0 / ns);
+    Ok(*TSC_NS.get_or_init(|| ns))
+}
+
+/// A histogram of latencies in ns of fixed size, splitting each power of two into `2^SUB_BITS`
+/// linear buckets as HDR histograms do, so that a bucket spans at most 1/32 of its values.
+struct Histogram {
+    counts: Vec<u64>,
+    max: u64,
+}
+impl Histogram {
+    const SUB_BITS: u32 = 5;
+    const BUCKETS: usize = (65 - Self::SUB_BITS as usize) << Self::SUB_BITS;
+
+    fn new() -> Self {
+        Self {
+            counts: vec![0; Self::BUCKETS],
+            max: 0,
+        }
+    }
+
+    fn bucket(ns: u64) -> usize {
+        let shift = (63 - (ns | 1).leading_zeros()).saturating_sub(Self::SUB_BITS);
+        ((shift as usize) << Self::SUB_BITS) + (ns >> shift) as usize
+    }
+
+    /// The lowest and highest value of a bucket.
+    fn range(bucket: usize) -> (u64, u64) {
+        let shift = (bucket >> Self::SUB_BITS).saturating_sub(1);
+        let low = ((bucket - (shift << Self::SUB_BITS)) as u64) << shift;
+        (low, low + (1 << shift) - 1)
+    }
+
+    fn record(&mut self, ns: u64) {
+        self.counts[Self::bucket(ns)] += 1;
+        self.max = self.max.max(ns);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (total, n) in self.counts.iter_mut().zip(&other.counts) {
+            *total += n;
+        }
+        self.max = self.max.max(other.max);
+    }
+
+    fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The highest value of the bucket holding the quantile, capped by the maximum.
+    fn quantile(&self, q: f64) -> u64 {
+        let rank = ((q * self.total() as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, &n) in self.counts.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Self::range(bucket).1.min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Appends the non-empty buckets to the CSV file of `--latency-out`.
+    fn dump(&self, path: &path::Path, iteration: usize) -> Result<()> {
+        let mut out = String::new();
+        for (bucket, &n) in self.counts.iter().enumerate().filter(|(_, &n)| n > 0) {
+            let (low, high) = Self::range(bucket);
+            out += &format!("{iteration},{low},{high},{n}\n");
+        }
+        let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+        file.write_all(out.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Set when the workers should stop before having performed all updates.
+static STOP: AtomicBool = AtomicBool::new(false);
+/// Set by the SIGINT handler, stopping the current iteration and skipping the rest.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+    STOP.store(true, Ordering::Relaxed);
+}
+/// The average latency of a hop of the pointer chase of the iteration in ns.
+static HOP_NS: sync::Mutex<Option<f64>> = sync::Mutex::new(None);
+/// Bytes read by the sequential read passes so far.
+static SCANNED: AtomicUsize = AtomicUsize::new(0);
+/// Bytes written by the workers so far.
+static WRITTEN: AtomicUsize = AtomicUsize::new(0);
+/// Set once the hot and cold regions have swapped roles, mirroring the indices drawn.
+static FLIPPED: AtomicBool = AtomicBool::new(false);
+/// Updates performed by each worker thread so far if `--per-thread`.
+static THREAD_UPDATES: sync::OnceLock<Box<[AtomicUsize]>> = sync::OnceLock::new();
+
+/// The updates of each thread per chunk of the DRAM portion since the previous report, counted
+/// alongside `--dram-ratio`.
+struct ChunkUpdates {
+    chunk_size: usize,
+    chunks: usize,
+    counts: Box<[AtomicUsize]>,
+}
+
+static CHUNK_UPDATES: sync::OnceLock<ChunkUpdates> = sync::OnceLock::new();
+/// The updates per bucket of elements for `--check-dist`.
+static DIST_BUCKETS: sync::OnceLock<ChunkUpdates> = sync::OnceLock::new();
+/// The updates per chunk for `--cdf-out`.
+static CDF_UPDATES: sync::OnceLock<ChunkUpdates> = sync::OnceLock::new();
+
+impl ChunkUpdates {
+    fn new(len: usize, chunk_size: usize, threads: usize) -> Self {
+        let chunks = len.div_ceil(chunk_size);
+        let counts = (0..threads * chunks).map(|_| AtomicUsize::new(0)).collect();
+        Self {
+            chunk_size,
+            chunks,
+            counts,
+        }
+    }
+
+    /// Counts the updates of the thread at the given offsets, summed up over the batch first so
+    /// that each chunk costs a single atomic add.
+    fn count(&self, t: usize, offsets: impl Iterator<Item = usize>) {
+        let last = self.chunks - 1;
+        CHUNK_BATCH.with_borrow_mut(|batch| {
+            batch.resize(self.chunks, 0);
+            // a shift for the chunks of a power of two
+            match self.chunk_size.is_power_of_two() {
+                true => {
+                    let shift = self.chunk_size.trailing_zeros();
+                    offsets.for_each(|offset| batch[(offset >> shift).min(last)] += 1);
+                }
+                false => {
+                    offsets.for_each(|offset| batch[(offset / self.chunk_size).min(last)] += 1);
+                }
+            }
+            let counts = &self.counts[t * self.chunks..][..self.chunks];
+            for (count, n) in counts.iter().zip(batch.iter_mut()).filter(|(_, n)| **n > 0) {
+                count.fetch_add(mem::take(n), Ordering::Relaxed);
+            }
+        });
+    }
+
+    /// The updates per chunk of all threads since the previous call.
+    fn take(&self) -> Vec<usize> {
+        let mut total = vec![0; self.chunks];
+        for counts in self.counts.chunks(self.chunks) {
+            for (total, count) in total.iter_mut().zip(counts) {
+                *total += count.swap(0, Ordering::Relaxed);
+            }
+        }
+        total
+    }
+}
+
+/// Counts the updates of the `t`-th worker thread for `--per-thread`.
+fn count_thread(t: usize, n: usize) {
+    if let Some(counts) = THREAD_UPDATES.get() {
+        counts[t].fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+/// The updates of each thread so far for `--per-thread`.
+fn thread_updates() -> Option<Vec<usize>> {
+    let counts = THREAD_UPDATES.get()?;
+    Some(counts.iter().map(|n| n.load(Ordering::Relaxed)).collect())
+}
+
+/// Appends a row per chunk of its updates, the portion of the updates up to it and that of the as
+/// many hottest chunks.
+fn cdf_dump(path: &path::Path, iteration: usize, updates: &[usize]) -> Result<()> {
+    let total = updates.iter().sum::<usize>().max(1) as f64;
+    let mut hottest = updates.to_vec();
+    hottest.sort_unstable_by(|a, b| b.cmp(a));
+    let (mut cdf, mut hottest_cdf, mut out) = (0, 0, String::new());
+    for (chunk, (&n, &hot)) in updates.iter().zip(&hottest).enumerate() {
+        (cdf, hottest_cdf) = (cdf + n, hottest_cdf + hot);
+        out += &format!(
+            "{iteration},{chunk},{n},{:.6},{:.6}\n",
+            cdf as f64 / total,
+            hottest_cdf as f64 / total
+        );
+    }
+    let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+    file.write_all(out.as_bytes())?;
+    Ok(())
+}
+
+/// Compares the updates per bucket since the previous call with the distribution expected of the
+/// workload, if known.
+fn dist_check(args: &Args, counts: &ChunkUpdates) {
+    let observed = counts.take();
+    let total: usize = observed.iter().sum();
+    let expected = match dist_cdf(args) {
+        Ok(cdf) => cdf,
+        Err(e) => {
+            tracing::info!("check-dist: not checked, {e}");
+            return;
+        }
+    };
+    if total == 0 {
+        tracing::info!("check-dist: not checked without updates");
+        return;
+    }
+    let nelems = args.region_len() / args.granularity.unit();
+    let (mut tvd, mut worst) = (0.0, (0, 0.0));
+    for (k, &n) in observed.iter().enumerate() {
+        let bucket = k * counts.chunk_size..((k + 1) * counts.chunk_size).min(nelems);
+        let p = expected(bucket.end) - expected(bucket.start);
+        let diff = n as f64 / total as f64 - p;
+        tvd += diff.abs() / 2.0;
+        if diff.abs() > f64::abs(worst.1) {
+            worst = (k, diff);
+        }
+    }
+    let (k, diff) = worst;
+    let verdict = match tvd <= args.check_dist_threshold {
+        true => "pass",
+        false => "fail",
+    };
+    let line = format!(
+        "check-dist: total variation distance {tvd:.6} over {} buckets of {total} updates {verdict} against {}, bucket {k} off by {diff:+.6}",
+        observed.len(),
+        args.check_dist_threshold
+    );
+    match tvd <= args.check_dist_threshold {
+        true => tracing::info!("{line}"),
+        false => tracing::warn!("{line}"),
+    }
+}
+
+/// The expected portion of the updates to the elements below each index, of the workloads whose
+/// distribution is known in closed form.
+fn dist_cdf(args: &Args) -> std::result::Result<Box<dyn Fn(usize) -> f64>, String> {
+    let (len, g) = (args.region_len(), args.granularity.unit());
+    let nelems = len / g;
+    let unsupported = [
+        ("a keyspace", args.keyspace.is_some()),
+        ("churn", args.churn_interval.is_some()),
+        ("partitioning", args.partitioned),
+        ("runs", args.run_length > 1),
+        ("repeats", args.repeat_prob > 0.0),
+    ];
+    if let Some((name, _)) = unsupported.iter().find(|(_, on)| *on) {
+        return Err(format!("the distribution is reshaped by {name}"));
+    }
+    let hole = args.hole().map_err(|e| e.to_string())?;
+    let n = nelems - hole.len();
+    // the portion below the index of the distribution over the elements outside of the hole
+    let holed = move |cdf: Box<dyn Fn(usize) -> f64>| -> Box<dyn Fn(usize) -> f64> {
+        Box::new(move |i| match i {
+            i if i <= hole.start => cdf(i),
+            i if i < hole.end => cdf(hole.start),
+            i => cdf(i - hole.len()),
+        })
+    };
+    match args.workload.clone() {
+        Workload::Random { .. } => Ok(holed(Box::new(move |i| i as f64 / n as f64))),
+        Workload::Zipf {
+            exponent,
+            reverse,
+            scramble: false,
+            ..
+        } => {
+            let mass = move |i: usize| zipf_mass(i, exponent) / zipf_mass(n, exponent);
+            Ok(holed(match reverse {
+                true => Box::new(move |i| 1.0 - mass(n - i)),
+                false => Box::new(mass),
+            }))
+        }
+        Workload::Hotset {
+            regions, reverse, ..
+        } if !regions.is_empty() => {
+            let layout = regions_layout(len, g, &regions).map_err(|e| e.to_string())?;
+            let total: usize = layout.iter().map(|&(_, _, w)| w).sum();
+            let segments = layout
+                .into_iter()
+                .map(|(_, r, w)| (r.start / g..r.end / g, w as f64 / total as f64))
+                .collect();
+            Ok(segments_cdf(segments, nelems, reverse))
+        }
+        Workload::Hotset {
+            hot,
+            weight,
+            offsets,
+            reverse,
+            shift_interval,
+            hot_grow,
+            hot_shrink,
+            write_region,
+            weight_schedule,
+            flip_at,
+            ..
+        } => {
+            if shift_interval.is_some() || hot_grow.or(hot_shrink).is_some() {
+                return Err("the hot regions move".into());
+            }
+            if !weight_schedule.is_empty() || flip_at.is_some() {
+                return Err("the weights change".into());
+            }
+            if !matches!(write_region, WriteRegion::Both) {
+                return Err("the writes are restricted to a region".into());
+            }
+            let hots = hotset_layout(len, g, &hot, &weight, &offsets).map_err(|e| e.to_string())?;
+            let hot: usize = hots.iter().map(|(r, _)| r.len() / g).sum();
+            let total: usize = hots.iter().map(|&(_, w)| w).sum();
+            // the cold elements share weight 1
+            let cold = match hot < nelems {
+                true => 1,
+                false => 0,
+            };
+            let mut segments = vec![];
+            let mut cursor = 0;
+            for (r, w) in hots {
+                let r = r.start / g..r.end / g;
+                if cursor < r.start {
+                    segments.push((
+                        cursor..r.start,
+                        (r.start - cursor) as f64 / (nelems - hot) as f64,
+                    ));
+                }
+                cursor = r.end;
+                segments.push((r, w as f64));
+            }
+            if cursor < nelems {
+                segments.push((
+                    cursor..nelems,
+                    (nelems - cursor) as f64 / (nelems - hot) as f64,
+                ));
+            }
+            let segments = segments
+                .into_iter()
+                .map(|(r, w)| (r, w / (total + cold) as f64))
+                .collect();
+            Ok(segments_cdf(segments, nelems, reverse))
+        }
+        workload => Err(format!("no expected distribution of {workload:?}")),
+    }
+}
+
+/// The portion below each index of the segments of elements with the given masses, uniform within.
+fn segments_cdf(
+    segments: Vec<(ops::Range<usize>, f64)>,
+    nelems: usize,
+    reverse: bool,
+) -> Box<dyn Fn(usize) -> f64> {
+    let cdf = move |i: usize| {
+        segments
+            .iter()
+            .filter(|(r, _)| r.start < i)
+            .map(|(r, mass)| mass * (r.end.min(i) - r.start) as f64 / r.len() as f64)
+            .sum::<f64>()
+    };
+    match reverse {
+        true => Box::new(move |i| 1.0 - cdf(nelems - i)),
+        false => Box::new(cdf),
+    }
+}
+
+/// The unnormalized zipf mass of the ranks below `n`, summed exactly over the first ranks and by
+/// the integral of the midpoints beyond.
+fn zipf_mass(n: usize, exponent: f64) -> f64 {
+    const EXACT: usize = 4096;
+    let exact: f64 = (1..=n.min(EXACT)).map(|k| (k as f64).powf(-exponent)).sum();
+    if n <= EXACT {
+        return exact;
+    }
+    let (a, b) = (EXACT as f64 + 0.5, n as f64 + 0.5);
+    let tail = match exponent == 1.0 {
+        true => (b / a).ln(),
+        false => (b.powf(1.0 - exponent) - a.powf(1.0 - exponent)) / (1.0 - exponent),
+    };
+    exact + tail
+}
+
+fn gups_do<D: Pattern + Sync>(
+    args: &Args,
+    mem: &mut [u8],
+    dist: D,
+    count_tx: mpsc::UnboundedSender<usize>,
+) -> Result<usize> {
+    gups_do_with(args, mem, &dist, &dist, None, count_tx)
+}
+
+/// Like `gups_do` but draws the indices of reads and writes from separate distributions. If
+/// `is_hot` is given, the bytes read and written are additionally accounted per hot and cold region.
+/// Returns the number of accesses performed, which falls short of the updates if stopped early.
+fn gups_do_with<D: Pattern + Sync, W: Pattern + Sync>(
+    args: &Args,
+    mem: &mut [u8],
+    dist: &D,
+    writes_dist: &W,
+    is_hot: Option<&(dyn Fn(usize) -> bool + Sync)>,
+    count_tx: mpsc::UnboundedSender<usize>,
+) -> Result<usize> {
+    let (updates, thread, granularity) = (args.update, args.thread, args.granularity.unit());
+    let (sizes, classes) = (&args.granularity.sizes, args.granularity.classes()?);
+    let op = args.op;
+    // the copy op writes its own size in place of the single update size
+    let sizes = &match op {
+        Op::Copy => vec![args.copy_size],
+        _ => sizes.clone(),
+    };
+    let written: Vec<_> = sizes.iter().map(|_| AtomicUsize::new(0)).collect();
+    let (read_ratio, repeat_prob) = (args.read_ratio, args.repeat_prob);
+    let churn = args.churn_interval.is_some();
+    // only the hotset flips, checking once per chunk of accesses
+    let flips = matches!(
+        args.workload,
+        Workload::Hotset {
+            flip_at: Some(_),
+            ..
+        }
+    );
+    let repeats = AtomicUsize::new(0);
+    let scan = args.scan_every.zip(args.scan_bytes);
+    let (partitioned, part) = (args.partitioned, args.region_len() / granularity);
+    // each logical access updates a run of elements or a stencil around its center, the updates
+    // counting the elements
+    let run_length = args.run_length;
+    let stencil = match args.workload {
+        Workload::Stencil { radius } => Some(radius),
+        _ => None,
+    };
+    let logical = AtomicUsize::new(0);
+    let key_seed = args.keyspace.map(|_| splitmix64(args.seed));
+    // the hot regions hold keys rather than elements
+    let is_hot = is_hot.filter(|_| key_seed.is_none());
+    let nelems = if partitioned {
+        part
+    } else {
+        args.region_len() / granularity
+    };
+    let per_thread: Vec<_> = (0..thread).map(|_| AtomicUsize::new(0)).collect();
+    let pace = args.arrival_rate.map(|rate| rate / thread as f64);
+    // about a millisecond worth of updates if paced, so that the counts arrive in time
+    let chunk_size = pace.map_or(4096, |rate| ((rate / 1000.0) as usize).clamp(1, 4096));
+    let (reads, writes) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    let sink = AtomicU64::new(0);
+    let checksums: Vec<_> = (0..thread).map(|_| AtomicU64::new(0)).collect();
+    // bytes read from and written to the hot and the cold region
+    let [hot_reads, hot_writes, cold_reads, cold_writes] = [(); 4].map(|_| AtomicUsize::new(0));
+    let layout = RegionLayout::new(args);
+    let region_accesses: Vec<_> = (0..layout.count).map(|_| AtomicUsize::new(0)).collect();
+    let recorder = match &args.record_trace {
+        Some(path) => Some(TraceWriter::spawn(path, args.record_sample)?),
+        None => None,
+    };
+    let sample = args.latency_sample;
+    let clock = match args.latency_rdtsc {
+        true => Clock::Tsc(tsc_ns()?),
+        false => Clock::Instant(time::Instant::now()),
+    };
+    let latencies: Option<Vec<_>> = sample.map(|_| {
+        (0..thread)
+            .map(|_| sync::Mutex::new(Histogram::new()))
+            .collect()
+    });
+    // the workers run on the CPUs local to their private regions
+    let local = match args.local_alloc {
+        true => Some(local_cpus(thread)?),
+        false => None,
+    };
+    let do_init = || {
+        // FIXME: We should be initializing each thread with a disjoint part of the memory
+        MEM.with(|m| {
+            let ptr = mem.as_ptr() as *mut _;
+            let mem = unsafe { slice::from_raw_parts_mut(ptr, mem.len()) };
+            m.replace(mem);
+        });
+    };
+    let do_work = || {
+        (0..updates / stencil.map_or(run_length, |r| 2 * r + 1))
+            .into_par_iter()
+            .map_init(
+                || {
+                    // the first element of the part of the executing thread
+                    let base = if partitioned {
+                        rayon::current_thread_index().unwrap() * part
+                    } else {
+                        0
+                    };
+                    let buffer = recorder.as_ref().map(TraceWriter::buffer);
+                    // the previous index of the thread, and whether flipped along with the
+                    // accesses left until checked again
+                    (rand::thread_rng(), base, buffer, None, (false, 0))
+                },
+                |(rng, base, buffer, prev, (flipped, left)), i| {
+                    let read =
+                        op == Op::Load || read_ratio > 0 && rng.gen_range(0..100) < read_ratio;
+                    let (index, repeat) = match *prev {
+                        Some(prev) if repeat_prob > 0.0 && rng.gen_bool(repeat_prob) => {
+                            (prev, true)
+                        }
+                        _ => {
+                            let index = if read {
+                                dist.index(rng, i)
+                            } else {
+                                writes_dist.index(rng, i)
+                            };
+                            let index = match key_seed {
+                                Some(seed) => {
+                                    (splitmix64(index as u64 ^ seed) % part as u64) as usize
+                                }
+                                None => index,
+                            };
+                            if flips {
+                                if *left == 0 {
+                                    (*flipped, *left) =
+                                        (FLIPPED.load(Ordering::Relaxed), chunk_size);
+                                }
+                                *left -= 1;
+                            }
+                            let index = match *flipped {
+                                true => part - 1 - index,
+                                false => index,
+                            };
+                            // fold into the currently live part, never exceeding the region
+                            let index = match churn {
+                                true => index % LIVE.load(Ordering::Relaxed),
+                                false => index,
+                            };
+                            (*base + index, false)
+                        }
+                    };
+                    *prev = Some(index);
+                    if let Some(buffer) = buffer {
+                        buffer.record(i, index, read);
+                    }
+                    let class = classes.as_ref().map_or(0, |c| c.sample(rng));
+                    (index, read, class, repeat)
+                },
+            )
+            .chunks(chunk_size)
+            .try_for_each(|accesses| {
+                if STOP.load(Ordering::Relaxed) {
+                    return None;
+                }
+                logical.fetch_add(accesses.len(), Ordering::Relaxed);
+                let accesses = match (run_length, stencil) {
+                    (1, None) => accesses,
+                    (n, None) => accesses
+                        .into_iter()
+                        .flat_map(|(index, read, class, repeat)| {
+                            // wrap within the part of the thread if partitioned
+                            let start = index / nelems * nelems;
+                            (0..n).map(move |k| {
+                                let index = start + (index - start + k) % nelems;
+                                (index, read, class, repeat)
+                            })
+                        })
+                        .collect(),
+                    (_, Some(r)) => accesses
+                        .into_iter()
+                        .flat_map(|(index, read, class, repeat)| {
+                            // clamp within the part of the thread if partitioned
+                            let start = index / nelems * nelems;
+                            let lo = index.saturating_sub(r).max(start);
+                            let hi = (index + r).min(start + nelems - 1);
+                            (lo..=hi).map(move |index| (index, read, class, repeat))
+                        })
+                        .collect(),
+                };
+                let (mut loaded, mut nreads) = (0u64, 0);
+                // the bytes written per size class and the latencies of the sampled updates in ns
+                let (mut bytes_written, mut samples) = CHUNK_SCRATCH.take();
+                bytes_written.clear();
+                bytes_written.resize(sizes.len(), 0);
+                let mut countdown = SINCE_SAMPLE.with(Cell::get);
+                // the arrival of each update if paced, outside of its timing
+                let wait = || {
+                    if let Some(rate) = pace {
+                        PACER.with(|p| {
+                            let t = rayon::current_thread_index().unwrap() as u64;
+                            p.borrow_mut()
+                                .get_or_insert_with(|| Pacer::new(rate, args.seed.wrapping_add(t)))
+                                .wait()
+                        });
+                    }
+                };
+                MEM.with(|m| {
+                    let mem = &mut **m.borrow_mut();
+                    let mut access = |op| {
+                        let mut each = |&(index, read, class, _): &(usize, bool, usize, bool)| {
+                            let size = sizes[class];
+                            if read {
+                                loaded =
+                                    loaded.wrapping_add(load_span(mem, granularity, index, size));
+                                nreads += 1;
+                            } else {
+                                bytes_written[class] +=
+                                    update_span(mem, granularity, index, size, op);
+                            }
+                        };
+                        // the untimed runs between the sampled updates keep the plain loop
+                        let every = sample.unwrap_or(usize::MAX);
+                        let mut rest = &accesses[..];
+                        loop {
+                            let (untimed, timed) =
+                                rest.split_at((every - 1 - countdown).min(rest.len()));
+                            untimed.iter().for_each(|access| {
+                                wait();
+                                each(access)
+                            });
+                            countdown += untimed.len();
+                            let Some((access, timed)) = timed.split_first() else {
+                                break;
+                            };
+                            wait();
+                            let start = clock.now();
+                            each(access);
+                            samples.push(clock.since(start));
+                            (countdown, rest) = (0, timed);
+                        }
+                    };
+                    // dispatch once per chunk so that each loop is specialized to its op
+                    match op {
+                        Op::Add => access(Op::Add),
+                        Op::Xor => access(Op::Xor),
+                        Op::Store => access(Op::Store),
+                        Op::Load => access(Op::Load),
+                        Op::Copy => access(Op::Copy),
+                    }
+                    if let Some((every, bytes)) = scan {
+                        let since = SINCE_SCAN.with(|c| c.get()) + accesses.len();
+                        for _ in 0..since / every {
+                            loaded = loaded.wrapping_add(scan_pass(mem, bytes));
+                        }
+                        SINCE_SCAN.with(|c| c.set(since % every));
+                    }
+                });
+                SINCE_SAMPLE.with(|c| c.set(countdown));
+                reads.fetch_add(nreads, Ordering::Relaxed);
+                writes.fetch_add(accesses.len() - nreads, Ordering::Relaxed);
+                if repeat_prob > 0.0 {
+                    let n = accesses.iter().filter(|&&(.., repeat)| repeat).count();
+                    repeats.fetch_add(n, Ordering::Relaxed);
+                }
+                if let Some(counts) = CHUNK_UPDATES.get() {
+                    let t = rayon::current_thread_index().unwrap();
+                    counts.count(t, accesses.iter().map(|&(index, ..)| index * granularity));
+                }
+                if let Some(counts) = DIST_BUCKETS.get() {
+                    let t = rayon::current_thread_index().unwrap();
+                    counts.count(t, accesses.iter().map(|&(index, ..)| index));
+                }
+                if let Some(counts) = CDF_UPDATES.get() {
+                    let t = rayon::current_thread_index().unwrap();
+                    counts.count(t, accesses.iter().map(|&(index, ..)| index * granularity));
+                }
+                WRITTEN.fetch_add(bytes_written.iter().sum(), Ordering::Relaxed);
+                for (total, &b) in written.iter().zip(&bytes_written) {
+                    total.fetch_add(b, Ordering::Relaxed);
+                }
+                if let Some(is_hot) = is_hot {
+                    let mut bytes = [0; 4];
+                    for &(index, read, class, _) in &accesses {
+                        let k = match (is_hot(index % part), read) {
+                            (true, true) => 0,
+                            (true, false) => 1,
+                            (false, true) => 2,
+                            (false, false) => 3,
+                        };
+                        bytes[k] += sizes[class];
+                    }
+                    for (total, b) in [&hot_reads, &hot_writes, &cold_reads, &cold_writes]
+                        .into_iter()
+                        .zip(bytes)
+                    {
+                        total.fetch_add(b, Ordering::Relaxed);
+                    }
+                }
+                if layout.count > 1 {
+                    let mut counts = vec![0; layout.count];
+                    for &(index, ..) in &accesses {
+                        counts[layout.locate(index * granularity).0] += 1;
+                    }
+                    for (total, n) in region_accesses.iter().zip(counts) {
+                        total.fetch_add(n, Ordering::Relaxed);
+                    }
+                }
+                sink.fetch_add(loaded, Ordering::Relaxed);
+                let t = rayon::current_thread_index().unwrap();
+                checksums[t].fetch_add(loaded, Ordering::Relaxed);
+                per_thread[t].fetch_add(accesses.len(), Ordering::Relaxed);
+                count_thread(t, accesses.len());
+                if let Some(latencies) = latencies.as_ref().filter(|_| !samples.is_empty()) {
+                    let mut latencies = latencies[t].lock().unwrap();
+                    samples.drain(..).for_each(|ns| latencies.record(ns));
+                }
+                CHUNK_SCRATCH.set((bytes_written, samples));
+                count_tx.unbounded_send(accesses.len()).unwrap();
+                Some(())
+            });
+    };
+    // the usage of each thread taken on its way out of the pool
+    let usages = sync::Mutex::new(vec![None; thread]);
+    let start = time::Instant::now();
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(thread)
+        .thread_name(|i| format!("gups-rayon-{}", i))
+        .build_scoped(
+            |thread| {
+                do_init();
+                let t = thread.index();
+                if let Some(local) = &local {
+                    let (cpu, _) = local[t];
+                    if let Err(e) = pin(&[cpu]) {
+                        tracing::warn!("thread {t} pin to cpu {cpu}: {e}");
+                    }
+                }
+                tracing::info!("thread {t:?} started");
+                thread.run();
+                match thread_rusage() {
+                    Ok(usage) => usages.lock().unwrap()[t] = Some(usage),
+                    Err(e) => tracing::warn!("thread {t} rusage: {e}"),
+                }
+            },
+            |pool| {
+                pool.install(do_work);
+            },
+        )?;
+    let elapsed = start.elapsed();
+    if let Some(recorder) = recorder {
+        tracing::info!("recorded {} accesses", recorder.finish()?);
+    }
+    // those of the threads whose usage is unknown left out
+    let usages: Vec<_> = (0..thread)
+        .zip(usages.into_inner().unwrap())
+        .filter_map(|(t, u)| Some((t, u?)))
+        .collect();
+    if !usages.is_empty() {
+        fn secs(t: libc::timeval) -> f64 {
+            t.tv_sec as f64 + t.tv_usec as f64 * 1e-6
+        }
+        let sum = |f: fn(&libc::rusage) -> f64| usages.iter().map(|(_, u)| f(u)).sum::<f64>();
+        let (t, most) = usages
+            .iter()
+            .map(|(t, u)| (*t, u.ru_nivcsw))
+            .max_by_key(|&(_, n)| n)
+            .unwrap();
+        tracing::info!(
+            "threads cpu user {:.3} s system {:.3} s context switches voluntary {} involuntary {}, most involuntary {most} by thread {t}",
+            sum(|u| secs(u.ru_utime)),
+            sum(|u| secs(u.ru_stime)),
+            sum(|u| u.ru_nvcsw as f64),
+            sum(|u| u.ru_nivcsw as f64),
+        );
+        if args.per_thread {
+            for (t, u) in &usages {
+                tracing::info!(
+                    "thread {t} cpu user {:.3} s system {:.3} s context switches voluntary {} involuntary {}",
+                    secs(u.ru_utime),
+                    secs(u.ru_stime),
+                    u.ru_nvcsw,
+                    u.ru_nivcsw
+                );
+            }
+        }
+    }
+    let per_thread: Vec<_> = per_thread
+        .into_iter()
+        .map(AtomicUsize::into_inner)
+        .collect();
+    if partitioned {
+        for (t, &n) in per_thread.iter().enumerate() {
+            let bytes = t * part * granularity..(t + 1) * part * granularity;
+            let rate = args.unit.show(args.unit.rate(n, elapsed.as_secs_f64()));
+            tracing::info!("thread {t} bytes {bytes:?} {} {rate}", args.unit);
+        }
+    }
+    if let Workload::Tenants {
+        per_thread_workload,
+    } = &args.workload
+    {
+        for ThreadGroup { threads, dist } in per_thread_workload {
+            let n: usize = per_thread[threads.clone()].iter().sum();
+            let rate = args.unit.show(args.unit.rate(n, elapsed.as_secs_f64()));
+            tracing::info!("tenant threads {threads:?} {dist:?} {} {rate}", args.unit);
+        }
+    }
+    tracing::info!(
+        "{} reads {} writes {} bytes written, read sink {:#x}",
+        reads.into_inner(),
+        writes.into_inner(),
+        written
+            .iter()
+            .map(|n| n.load(Ordering::Relaxed))
+            .sum::<usize>(),
+        sink.into_inner()
+    );
+    if let Some(latencies) = latencies {
+        let mut merged = Histogram::new();
+        for latencies in latencies {
+            merged.merge(&latencies.into_inner().unwrap());
+        }
+        let percentiles: Vec<_> = [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("p99.9", 0.999)]
+            .map(|(name, q)| format!("{name} {}", merged.quantile(q)))
+            .into();
+        tracing::info!(
+            "latency ns {} max {} of {} sampled updates",
+            percentiles.join(" "),
+            merged.max,
+            merged.total()
+        );
+        if let Some(path) = &args.latency_out {
+            merged.dump(path, ITERATIONS.load(Ordering::Relaxed))?;
+        }
+    }
+    let logical = logical.into_inner();
+    if run_length > 1 {
+        let rate = logical as f64 / elapsed.as_secs_f64();
+        tracing::info!("{logical} logical accesses of {run_length} elements, {rate:.0} per second");
+    }
+    if stencil.is_some() {
+        let average = per_thread.iter().sum::<usize>() as f64 / logical as f64;
+        tracing::info!("{logical} stencil centers, {average:.3} updates per center");
+    }
+    if op == Op::Load {
+        for (t, checksum) in checksums.into_iter().enumerate() {
+            tracing::info!("thread {t} checksum {:#x}", checksum.into_inner());
+        }
+    }
+    if repeat_prob > 0.0 {
+        let fraction = repeats.into_inner() as f64 / per_thread.iter().sum::<usize>() as f64;
+        tracing::info!("repeated the previous index for {fraction:.4} of the updates");
+    }
+    if sizes.len() > 1 {
+        let written: Vec<_> = sizes
+            .iter()
+            .zip(written)
+            .map(|(size, n)| format!("{size}: {}", n.into_inner()))
+            .collect();
+        tracing::info!("bytes written per update size {}", written.join(", "));
+    }
+    if is_hot.is_some() {
+        tracing::info!(
+            "hot region read {} bytes written {} bytes, cold region read {} bytes written {} bytes",
+            hot_reads.into_inner(),
+            hot_writes.into_inner(),
+            cold_reads.into_inner(),
+            cold_writes.into_inner()
+        );
+    }
+    if layout.count > 1 {
+        let accesses: Vec<_> = region_accesses
+            .into_iter()
+            .map(|n| n.into_inner())
+            .collect();
+        tracing::info!("accesses per region {accesses:?}");
+    }
+    if let Some(counts) = DIST_BUCKETS.get() {
+        dist_check(args, counts);
+    }
+    if let (Some(counts), Some(path)) = (CDF_UPDATES.get(), &args.cdf_out) {
+        cdf_dump(path, ITERATIONS.load(Ordering::Relaxed), &counts.take())?;
+    }
+    Ok(per_thread.into_iter().sum())
+}
+
+/// Size of a recorded access: the element index and update sequence number as little-endian 64-bit
+/// integers, the thread index as a 16-bit one and the op byte, 0 for a write and 1 for a read.
+const TRACE_RECORD: usize = 19;
+
+/// Writes the accesses recorded into per-thread buffers to the trace on a dedicated thread, so that
+/// the workers never wait on the file.
+struct TraceWriter {
+    tx: sync::mpsc::Sender<Vec<u8>>,
+    writer: std::thread::JoinHandle<io::Result<usize>>,
+    sample: usize,
+}
+
+struct TraceBuffer {
+    tx: sync::mpsc::Sender<Vec<u8>>,
+    buf: Vec<u8>,
+    sample: usize,
+    thread: u16,
+}
+
+impl TraceWriter {
+    fn spawn(path: &path::Path, sample: usize) -> Result<Self> {
+        let file = std::fs::OpenOptions::new().append(true).open(path)?;
+        let mut file = io::BufWriter::new(file);
+        let (tx, rx) = sync::mpsc::channel::<Vec<u8>>();
+        let writer = std::thread::Builder::new()
+            .name("gups-trace".into())
+            .spawn(move || {
+                let mut bytes = 0;
+                for buf in rx {
+                    file.write_all(&buf)?;
+                    bytes += buf.len();
+                }
+                file.flush()?;
+                Ok(bytes / TRACE_RECORD)
+            })?;
+        Ok(Self { tx, writer, sample })
+    }
+
+    /// A buffer for the calling worker thread.
+    fn buffer(&self) -> TraceBuffer {
+        TraceBuffer {
+            tx: self.tx.clone(),
+            buf: Vec::with_capacity(TRACE_RECORD << 12),
+            sample: self.sample,
+            thread: rayon::current_thread_index().unwrap_or_default() as u16,
+        }
+    }
+
+    /// Waits for all buffers to be written, returning the number of accesses recorded.
+    fn finish(self) -> Result<usize> {
+        drop(self.tx);
+        Ok(self.writer.join().unwrap()?)
+    }
+}
+
+impl TraceBuffer {
+    fn record(&mut self, seq: usize, index: usize, read: bool) {
+        if seq % self.sample != 0 {
+            return;
+        }
+        self.buf.extend((index as u64).to_le_bytes());
+        self.buf.extend((seq as u64).to_le_bytes());
+        self.buf.extend(self.thread.to_le_bytes());
+        self.buf.push(read as u8);
+        if self.buf.len() + TRACE_RECORD > self.buf.capacity() {
+            let buf = Vec::with_capacity(self.buf.capacity());
+            self.tx.send(mem::replace(&mut self.buf, buf)).unwrap();
+        }
+    }
+}
+
+impl Drop for TraceBuffer {
+    fn drop(&mut self) {
+        if !self.buf.is_empty() {
+            self.tx.send(mem::take(&mut self.buf)).unwrap();
+        }
+    }
+}
+
+fn trace_dump(path: &path::Path) -> Result<()> {
+    let mut trace = io::BufReader::new(std::fs::File::open(path)?);
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    let mut record = [0; TRACE_RECORD];
+    loop {
+        match trace.read_exact(&mut record) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let index = u64::from_le_bytes(record[..8].try_into().unwrap());
+        let seq = u64::from_le_bytes(record[8..16].try_into().unwrap());
+        let thread = u16::from_le_bytes(record[16..18].try_into().unwrap());
+        let op = if record[18] != 0 { "r" } else { "w" };
+        match writeln!(out, "{index},{op},{thread},{seq}") {
+            // e.g. piped into `head`
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            res => res?,
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Sums up `len` bytes starting at a random page of `mem`.
+fn scan_pass(mem: &[u8], len: usize) -> u64 {
+    let pages = (mem.len() - len) / *PAGE_SIZE;
+    let start = rand::thread_rng().gen_range(0..=pages) * *PAGE_SIZE;
+    let sum = mem[start..start + len]
+        .chunks(8)
+        .map(|w| {
+            let mut word = [0; 8];
+            word[..w.len()].copy_from_slice(w);
+            u64::from_ne_bytes(word)
+        })
+        .fold(0u64, u64::wrapping_add);
+    SCANNED.fetch_add(len, Ordering::Relaxed);
+    sum
+}
+
+/// Links all elements into a single random cycle, with the first 8 bytes of each element holding
+/// the index of its successor. The order is obtained by sorting the indices by a seeded hash, which
+/// is a bijection and therefore yields the same cycle regardless of the number of threads.
+fn chase_init(mem: &mut [u8], granularity: usize, seed: u64) -> Result<()> {
+    // the counter after the link keeps the hops writing
+    if granularity < 16 {
+        return Err(
+            format!("pointer chase needs a granularity of at least 16, got {granularity}").into(),
+        );
+    }
+    let start = time::Instant::now();
+    let nelems = mem.len() / granularity;
+    let mut order: Vec<usize> = (0..nelems).into_par_iter().collect();
+    order.par_sort_unstable_by_key(|&i| splitmix64(seed ^ i as u64));
+    let words = unsafe { slice::from_raw_parts(mem.as_ptr() as *const AtomicU64, mem.len() / 8) };
+    let stride = granularity / 8;
+    (0..nelems).into_par_iter().for_each(|k| {
+        let (cur, next) = (order[k], order[(k + 1) % nelems]);
+        words[cur * stride].store(next as u64, Ordering::Relaxed);
+    });
+    tracing::info!(
+        "pointer chase cycle over {nelems} elements built in {:?}",
+        start.elapsed()
+    );
+    Ok(())
+}
+
+/// Each thread follows the cycle built by `chase_init` for `hops` steps, starting at evenly spaced
+/// positions, incrementing the second word of each element on its visit. Returns the hops each
+/// thread performed and the time it took.
+fn chase_do(
+    hops: usize,
+    thread: usize,
+    granularity: usize,
+    mem: &mut [u8],
+    count_tx: &mpsc::UnboundedSender<usize>,
+) -> Result<Vec<(usize, time::Duration)>> {
+    let chunk_size = 4096;
+    let stride = granularity / 8;
+    let nelems = mem.len() / granularity;
+    let ptr = mem.as_mut_ptr() as usize;
+    let len = mem.len();
+    let do_work = |ctx: rayon::BroadcastContext| {
+        let words = unsafe { slice::from_raw_parts_mut(ptr as *mut u64, len / 8) };
+        let mut cur = ctx.index() * (nelems / ctx.num_threads());
+        let start = time::Instant::now();
+        let mut done = 0;
+        while done < hops && !STOP.load(Ordering::Relaxed) {
+            let n = chunk_size.min(hops - done);
+            for _ in 0..n {
+                let i = cur * stride;
+                words[i + 1] = words[i + 1].wrapping_add(1);
+                cur = words[i] as usize;
+            }
+            done += n;
+            count_thread(ctx.index(), n);
+            count_tx.unbounded_send(n).unwrap();
+        }
+        (done, start.elapsed())
+    };
+    Ok(rayon::ThreadPoolBuilder::new()
+        .num_threads(thread)
+        .thread_name(|i| format!("gups-rayon-{}", i))
+        .build()?
+        .broadcast(do_work))
+}
+
+const HPCC_POLY: u64 = 0x7;
+const HPCC_PERIOD: u64 = 1317624576693539401;
+
+fn hpcc_next(ran: u64) -> u64 {
+    (ran << 1) ^ if (ran as i64) < 0 { HPCC_POLY } else { 0 }
+}
+
+/// The `n`-th value of the HPCC RandomAccess sequence, as `HPCC_starts` of the reference code.
+fn hpcc_starts(n: u64) -> u64 {
+    let n = n % HPCC_PERIOD;
+    if n == 0 {
+        return 1;
+    }
+    let mut m2 = [0; 64];
+    let mut temp = 1;
+    for m in m2.iter_mut() {
+        *m = temp;
+        temp = hpcc_next(hpcc_next(temp));
+    }
+    let mut i = 63 - n.leading_zeros() as i32;
+    let mut ran = 2;
+    while i > 0 {
+        ran = (0..64)
+            .filter(|j| (ran >> j) & 1 == 1)
+            .fold(0, |acc, j| acc ^ m2[j]);
+        i -= 1;
+        if (n >> i) & 1 == 1 {
+            ran = hpcc_next(ran);
+        }
+    }
+    ran
+}
+
+/// The largest power-of-two prefix of the memory region viewed as 64-bit words.
+fn hpcc_table(mem: &mut [u8]) -> &mut [u64] {
+    let len = 1 << (usize::BITS - 1 - (mem.len() / 8).leading_zeros());
+    unsafe { slice::from_raw_parts_mut(mem.as_mut_ptr() as *mut u64, len) }
+}
+
+fn hpcc_init(mem: &mut [u8], granularity: usize) -> Result<()> {
+    if granularity != 8 {
+        return Err(format!("hpcc needs a granularity of 8, got {granularity}").into());
+    }
+    let table = hpcc_table(mem);
+    table
+        .par_iter_mut()
+        .enumerate()
+        .for_each(|(i, t)| *t = i as u64);
+    tracing::info!("hpcc table of {} words", table.len());
+    Ok(())
+}
+
+/// Each thread xors its own contiguous part of the sequence into the table, without any
+/// synchronization as the benchmark rules allow.
+fn hpcc_do(
+    updates: usize,
+    thread: usize,
+    table: &mut [u64],
+    count_tx: mpsc::UnboundedSender<usize>,
+) -> Result<()> {
+    let chunk_size = 4096;
+    let (ptr, len) = (table.as_mut_ptr() as usize, table.len());
+    let do_work = |ctx: rayon::BroadcastContext| {
+        let table = unsafe { slice::from_raw_parts_mut(ptr as *mut u64, len) };
+        let (mask, per) = (len as u64 - 1, updates / ctx.num_threads());
+        let mut ran = hpcc_starts((ctx.index() * per) as u64);
+        let mut done = 0;
+        while done < per && !STOP.load(Ordering::Relaxed) {
+            let n = chunk_size.min(per - done);
+            for _ in 0..n {
+                ran = hpcc_next(ran);
+                table[(ran & mask) as usize] ^= ran;
+            }
+            done += n;
+            count_thread(ctx.index(), n);
+            count_tx.unbounded_send(n).unwrap();
+        }
+    };
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(thread)
+        .thread_name(|i| format!("gups-rayon-{}", i))
+        .build()?
+        .broadcast(do_work);
+    Ok(())
+}
+
+/// A memory mapped trace of element indices, each optionally tagged as a read.
+struct Trace {
+    map: memmap2::Mmap,
+    /// Size of a binary record, or `None` for CSV
+    record: Option<usize>,
+}
+
+impl Trace {
+    fn open(path: &path::Path, op: bool) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let record = match path.extension() {
+            Some(ext) if ext == "csv" => None,
+            _ => Some(if op { 9 } else { 8 }),
+        };
+        if let Some(size) = record {
+            if map.len() % size != 0 {
+                return Err(format!(
+                    "trace {} of {} bytes is not made of {size}-byte records",
+                    path.display(),
+                    map.len()
+                )
+                .into());
+            }
+        }
+        Ok(Self { map, record })
+    }
+
+    /// Splits the trace into `n` contiguous blocks of roughly the same size, at record boundaries.
+    fn blocks(&self, n: usize) -> Vec<ops::Range<usize>> {
+        let len = self.map.len();
+        let bounds: Vec<_> = (0..=n)
+            .map(|i| match self.record {
+                Some(size) => len / size * i / n * size,
+                None if i == 0 || i == n => i / n * len,
+                None => match self.map[len * i / n..].iter().position(|&b| b == b'\n') {
+                    Some(nl) => len * i / n + nl + 1,
+                    None => len,
+                },
+            })
+            .collect();
+        bounds.windows(2).map(|w| w[0]..w[1].max(w[0])).collect()
+    }
+
+    /// The records within `bytes`, as element index and whether it is read.
+    fn records(
+        &self,
+        bytes: ops::Range<usize>,
+    ) -> Box<dyn Iterator<Item = Result<(u64, bool)>> + '_> {
+        let data = &self.map[bytes];
+        match self.record {
+            Some(size) => Box::new(data.chunks_exact(size).map(|r| {
+                let index = u64::from_le_bytes(r[..8].try_into().unwrap());
+                Ok((index, r.get(8).is_some_and(|&op| op != 0)))
+            })),
+            None => Box::new(
+                data.split(|&b| b == b'\n')
+                    .map(|line| String::from_utf8_lossy(line).trim().to_owned())
+                    .filter(|line| !line.is_empty())
+                    .map(|line| {
+                        let mut fields = line.split(',').map(str::trim);
+                        let index = fields.next().unwrap_or_default().parse()?;
+                        let read = match fields.next() {
+                            None | Some("w") | Some("W") => false,
+                            Some("r") | Some("R") => true,
+                            Some(op) => return Err(format!("unknown op {op:?}").into()),
+                        };
+                        Ok((index, read))
+                    }),
+            ),
+        }
+    }
+
+    /// Parses the whole trace, returning the number of records and of those out of range.
+    fn check(&self, thread: usize, nelems: u64) -> Result<(usize, usize)> {
+        self.blocks(thread)
+            .into_par_iter()
+            .map(|block| {
+                let start = block.start;
+                self.records(block)
+                    .try_fold((0, 0), |(records, outside), record| {
+                        let (index, _) = record.map_err(|e| {
+                            format!("malformed record in block at byte {start}: {e}")
+                        })?;
+                        Ok((records + 1, outside + (index >= nelems) as usize))
+                    })
+            })
+            .try_reduce(|| (0, 0), |a, b| Ok((a.0 + b.0, a.1 + b.1)))
+    }
+}
+
+/// Each thread replays its block of the trace in order, dropping out-of-range indices unless
+/// `wrap` is set. Returns the number of records replayed.
+fn replay_do(
+    trace: &Trace,
+    thread: usize,
+    granularity: usize,
+    wrap: bool,
+    op: Op,
+    mem: &mut [u8],
+    count_tx: mpsc::UnboundedSender<usize>,
+) -> Result<usize> {
+    let chunk_size = 4096;
+    let nelems = (mem.len() / granularity) as u64;
+    let (ptr, len) = (mem.as_mut_ptr() as usize, mem.len());
+    let blocks = trace.blocks(thread);
+    let sink = AtomicU64::new(0);
+    let do_work = |ctx: rayon::BroadcastContext| {
+        let mem = unsafe { slice::from_raw_parts_mut(ptr as *mut u8, len) };
+        let (mut loaded, mut replayed, mut n) = (0u64, 0, 0);
+        for (index, read) in trace.records(blocks[ctx.index()].clone()).flatten() {
+            let index = match index {
+                i if i < nelems => i,
+                i if wrap => i % nelems,
+                _ => continue,
+            } as usize;
+            if read || op == Op::Load {
+                loaded = loaded.wrapping_add(load(mem, granularity, index));
+            } else {
+                update(mem, granularity, index, op);
+            }
+            n += 1;
+            if n == chunk_size {
+                count_thread(ctx.index(), n);
+                count_tx.unbounded_send(n).unwrap();
+                (replayed, n) = (replayed + n, 0);
+                if STOP.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+        }
+        count_thread(ctx.index(), n);
+        count_tx.unbounded_send(n).unwrap();
+        sink.fetch_add(loaded, Ordering::Relaxed);
+        replayed + n
+    };
+    let replayed = rayon::ThreadPoolBuilder::new()
+        .num_threads(thread)
+        .thread_name(|i| format!("gups-rayon-{}", i))
+        .build()?
+        .broadcast(do_work);
+    tracing::info!("read sink {:#x}", sink.into_inner());
+    Ok(replayed.into_iter().sum())
+}
+
+/// Applies the same updates again, this time atomically, which restores the initial table apart
+/// from the updates lost to races in `hpcc_do`. Returns the number of entries in error.
+fn hpcc_verify(updates: usize, thread: usize, table: &mut [u64]) -> Result<usize> {
+    let words = unsafe { slice::from_raw_parts(table.as_ptr() as *const AtomicU64, table.len()) };
+    let mask = table.len() as u64 - 1;
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(thread)
+        .build()?
+        .broadcast(|ctx| {
+            let per = updates / ctx.num_threads();
+            let mut ran = hpcc_starts((ctx.index() * per) as u64);
+            for _ in 0..per {
+                ran = hpcc_next(ran);
+                words[(ran & mask) as usize].fetch_xor(ran, Ordering::Relaxed);
+            }
+        });
+    // repair the entries in error so that the next iteration starts from a clean table
+    Ok(table
+        .par_iter_mut()
+        .enumerate()
+        .filter(|(i, t)| **t != *i as u64)
+        .map(|(i, t)| *t = i as u64)
+        .count())
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Cycles the weight of the hot regions through the steps until cancelled.
+async fn weight_schedule_actor(label: String, steps: Vec<WeightStep>) {
+    let start = time::Instant::now();
+    for WeightStep { weight, secs } in steps.into_iter().cycle() {
+        HOT_WEIGHT.store(weight, Ordering::Relaxed);
+        let elapsed = start.elapsed();
+        tracing::info!("iteration {label} hot weight set to {weight} at {elapsed:?}");
+        async_std::task::sleep(time::Duration::from_secs_f64(secs)).await;
+    }
+}
+
+/// The whole pages of the memory region tracked by `--track-protect`, with a bit per group of
+/// pages set by the SIGSEGV handler on its first access since the last protection.
+struct Tracked {
+    base: usize,
+    page: usize,
+    pages: usize,
+    /// Pages unprotected together, so that the mappings split by the handler stay within
+    /// `vm.max_map_count`.
+    group: usize,
+    bitmap: Box<[AtomicU64]>,
+}
+
+static TRACKED: sync::OnceLock<Tracked> = sync::OnceLock::new();
+/// Number of the next bitmap dumped.
+static TRACK_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// Allocates the bitmap of `--track-protect` and installs the SIGSEGV handler setting it.
+fn track_setup(args: &Args, mem: &[u8]) -> Result<()> {
+    if args.track_protect.is_none() {
+        return Ok(());
+    }
+    if args.track_protect == Some(0) {
+        return Err("tracking interval must be at least 1 ms".into());
+    }
+    let (page, base) = (args.page_size(), mem.as_ptr() as usize);
+    if base % page != 0 || mem.len() < page {
+        return Err(format!("memory region at {base:#x} has no whole page to track").into());
+    }
+    std::fs::create_dir_all(&args.track_dir)
+        .map_err(|e| format!("{}: {e}", args.track_dir.display()))?;
+    // each unprotected group may split off a mapping of its own, leaving half of the mappings
+    // allowed to the rest of the process
+    let max_map_count: usize = std::fs::read_to_string("/proc/sys/vm/max_map_count")
+        .map_err(|e| format!("/proc/sys/vm/max_map_count: {e}"))?
+        .trim()
+        .parse()?;
+    let pages = mem.len() / page;
+    let group = pages.div_ceil((max_map_count / 2).max(1));
+    if group > 1 {
+        tracing::warn!(
+            "track: {pages} pages exceed half of vm.max_map_count {max_map_count}, tracked in groups of {group}"
+        );
+    }
+    // the handler never allocates, so the bitmap is allocated up front
+    let bitmap = (0..pages.div_ceil(group).div_ceil(64))
+        .map(|_| AtomicU64::new(0))
+        .collect();
+    let tracked = Tracked {
+        base,
+        page,
+        pages,
+        group,
+        bitmap,
+    };
+    if TRACKED.set(tracked).is_err() {
+        return Err("memory region already tracked".into());
+    }
+    let mut action: libc::sigaction = unsafe { mem::zeroed() };
+    action.sa_sigaction = track_fault as *const () as libc::sighandler_t;
+    action.sa_flags = libc::SA_SIGINFO;
+    if unsafe { libc::sigaction(libc::SIGSEGV, &action, std::ptr::null_mut()) } != 0 {
+        return Err(format!("sigaction: {}", io::Error::last_os_error()).into());
+    }
+    tracing::info!("track: {pages} pages of {page} bytes");
+    Ok(())
+}
+
+/// Records the group of the faulting page of the memory region and unprotects it. Being a signal
+/// handler, it only touches atomics and makes system calls.
+extern "C" fn track_fault(_: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+    let addr = unsafe { (*info).si_addr() } as usize;
+    let unprotected = TRACKED.get().is_some_and(|t| {
+        if !(t.base..t.base + t.pages * t.page).contains(&addr) {
+            return false;
+        }
+        let group = (addr - t.base) / t.page / t.group;
+        t.bitmap[group / 64].fetch_or(1 << (group % 64), Ordering::Relaxed);
+        let start = group * t.group;
+        let len = t.group.min(t.pages - start) * t.page;
+        let prot = libc::PROT_READ | libc::PROT_WRITE;
+        unsafe { libc::mprotect((t.base + start * t.page) as _, len, prot) == 0 }
+    });
+    if !unprotected {
+        // a genuine fault, which recurs on return to the default action
+        unsafe { libc::signal(libc::SIGSEGV, libc::SIG_DFL) };
+    }
+}
+
+/// Sets the protection of the tracked pages.
+fn track_protect(prot: libc::c_int) -> Result<&'static Tracked> {
+    let t = TRACKED.get().unwrap();
+    if unsafe { libc::mprotect(t.base as _, t.pages * t.page, prot) } != 0 {
+        return Err(format!("mprotect: {}", io::Error::last_os_error()).into());
+    }
+    Ok(t)
+}
+
+/// Protects the memory region again, or unprotects it unless `again`, and dumps the pages
+/// accessed since the last dump. Pages first accessed between the two are accounted to the
+/// interval ending.
+fn track_dump(label: &str, dir: &path::Path, again: bool) -> Result<()> {
+    let t = track_protect(match again {
+        true => libc::PROT_NONE,
+        false => libc::PROT_READ | libc::PROT_WRITE,
+    })?;
+    let groups: Vec<_> = t
+        .bitmap
+        .iter()
+        .map(|w| w.swap(0, Ordering::Relaxed))
+        .collect();
+    // a bit per page in the dump regardless of the groups
+    let mut words = vec![0u64; t.pages.div_ceil(64)];
+    for p in 0..t.pages {
+        let g = p / t.group;
+        words[p / 64] |= (groups[g / 64] >> (g % 64) & 1) << (p % 64);
+    }
+    let touched: u32 = words.iter().map(|w| w.count_ones()).sum();
+    let seq = TRACK_SEQ.fetch_add(1, Ordering::Relaxed);
+    let path = dir.join(format!("track-{seq}.bin"));
+    let bytes: Vec<_> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    std::fs::write(&path, bytes).map_err(|e| format!("{}: {e}", path.display()))?;
+    tracing::info!(
+        "track: iteration {label} touched {touched} of {} pages dumped to {}",
+        t.pages,
+        path.display()
+    );
+    Ok(())
+}
+
+async fn track_actor(label: String, interval: time::Duration, dir: path::PathBuf) {
+    loop {
+        async_std::task::sleep(interval).await;
+        if let Err(e) = track_dump(&label, &dir, true) {
+            tracing::warn!("track: iteration {label} {e}");
+        }
+    }
+}
+
+/// The settings of the reports of an iteration, taken from the arguments.
+struct ReportConfig {
+    gups_dur: time::Duration,
+    unit: Unit,
+    ratio_dur: time::Duration,
+    dram: bool,
+    soft_dirty: bool,
+    idle: bool,
+    chunk_size: usize,
+    residency: Option<path::PathBuf>,
+    numa: bool,
+    thp: bool,
+    shift: Option<HotShift>,
+    growth: Option<HotGrowth>,
+    churn: Option<Churn>,
+    flip: Option<usize>,
+    pace: Option<f64>,
+    scan: bool,
+    page_size: usize,
+    overcommit: bool,
+    smaps: bool,
+    bytes: Option<(f64, f64)>,
+    copy: bool,
+    vmstat_fields: Vec<String>,
+    quiet: bool,
+    verbose: bool,
+    budget: Option<usize>,
+}
+
+impl ReportConfig {
+    /// The settings of an iteration stopped after `duration` if given.
+    fn new(args: &Args, duration: Option<time::Duration>) -> Result<Self> {
+        let g = args.granularity.unit();
+        // the pagemap is read once for both
+        let sample = args.dram_ratio.or(args.soft_dirty).or(args.idle_track);
+        let shift = match args.workload {
+            Workload::Hotset {
+                shift_interval: Some(ms),
+                shift_step,
+                ..
+            } => Some(HotShift {
+                interval: time::Duration::from_millis(ms),
+                step: shift_step / g,
+                len: args.region_len() / g,
+                granularity: g,
+            }),
+            _ => None,
+        };
+        let churn =
+            args.churn_interval
+                .zip(args.churn_range)
+                .map(|(ms, ChurnRange { min, max })| Churn {
+                    interval: time::Duration::from_millis(ms),
+                    min: min.div_ceil(g),
+                    max: max / g,
+                    granularity: g,
+                });
+        let growth = match &args.workload {
+            Workload::Hotset {
+                hot,
+                weight,
+                offsets,
+                hot_grow,
+                hot_shrink,
+                ..
+            } if hot_grow.or(*hot_shrink).is_some() => {
+                let len = args.region_len();
+                let hots = hotset_layout(len, g, hot, weight, offsets)?;
+                let [(range, _)] = &hots[..] else {
+                    return Err("only a single hot region can grow or shrink".into());
+                };
+                let rate = match (hot_grow, hot_shrink) {
+                    (Some(bytes), _) => *bytes as f64,
+                    (_, Some(bytes)) => -(*bytes as f64),
+                    _ => unreachable!(),
+                };
+                Some(HotGrowth {
+                    initial: range.len() / g,
+                    max: len / g - range.start / g,
+                    rate: rate / g as f64,
+                    granularity: g,
+                })
+            }
+            _ => None,
+        };
+        let flip = match args.workload {
+            Workload::Hotset {
+                flip_at: Some(_), ..
+            } if duration.is_some() => {
+                return Err("flipping needs an update budget rather than a duration".into())
+            }
+            Workload::Hotset {
+                flip_at: Some(f), ..
+            } => Some((f * args.update as f64) as usize),
+            _ => None,
+        };
+        Ok(Self {
+            gups_dur: time::Duration::from_millis(u64::MAX.min(args.report.unwrap_or(u64::MAX))),
+            unit: args.unit,
+            ratio_dur: time::Duration::from_millis(u64::MAX.min(sample.unwrap_or(u64::MAX))),
+            dram: args.dram_ratio.is_some(),
+            soft_dirty: args.soft_dirty.is_some(),
+            idle: args.idle_track.is_some(),
+            chunk_size: args.dram_chunk,
+            residency: args.residency_dump.clone(),
+            numa: args.numa_maps,
+            thp: args.thp_ratio,
+            shift,
+            growth,
+            churn,
+            flip,
+            pace: args.arrival_rate,
+            scan: args.scan_every.is_some(),
+            page_size: args.page_size(),
+            overcommit: args.allow_overcommit,
+            smaps: args.smaps,
+            bytes: args.bytes_per_update(),
+            copy: args.op == Op::Copy,
+            vmstat_fields: match args.vmstat {
+                true => args.vmstat_fields(),
+                false => Vec::new(),
+            },
+            quiet: args.quiet,
+            verbose: args.verbose,
+            // unknown for a phase of a duration
+            budget: (duration.is_none() && args.update != usize::MAX).then_some(args.update),
+        })
+    }
+}
+
+async fn reporting_actor(
+    label: &str,
+    mut count: mpsc::UnboundedReceiver<usize>,
+    config: ReportConfig,
+    regions: Vec<pagemap::MemoryRegion>,
+    logical: Option<LogicalPages>,
+) -> IterationStats {
+    let ReportConfig {
+        gups_dur,
+        unit,
+        ratio_dur,
+        dram,
+        soft_dirty,
+        idle,
+        chunk_size,
+        residency,
+        numa,
+        thp,
+        shift,
+        growth,
+        churn,
+        flip,
+        pace,
+        scan,
+        page_size,
+        overcommit,
+        smaps,
+        bytes,
+        copy,
+        vmstat_fields,
+        quiet,
+        verbose,
+        budget,
+    } = config;
+    // the periodic reports, left out by `--quiet`
+    macro_rules! report {
+        ($($arg:tt)*) => {
+            if !quiet {
+                tracing::info!($($arg)*)
+            }
+        };
+    }
+    let region = mem_span(&regions);
+    // the chunks of the DRAM portion named as before unless resized
+    let per = match chunk_size {
+        c if c == 1 << 30 => "gb".to_owned(),
+        c => format!("chunk of {c} bytes"),
+    };
+    // the estimated bandwidth of the given updates over the given time
+    let bandwidth = |updates: usize, secs: f64| {
+        bytes.map_or(String::new(), |(r, w)| {
+            let (r, w) = (
+                updates as f64 * r / secs / 1e9,
+                updates as f64 * w / secs / 1e9,
+            );
+            format!(" estimated read {r:.3} GB/s write {w:.3} GB/s")
+        })
+    };
+    // the faults and the swap usage of the process, read off the select loop
+    let usage = || (rusage().ok(), status_kb("/proc/self/status", "VmSwap").ok());
+    let gups_intvl = stream::interval(gups_dur)
+        .fuse()
+        .then(|_| async_std::task::spawn_blocking(usage));
+    pin_mut!(gups_intvl);
+    let shift_dur = shift.map_or(time::Duration::from_millis(u64::MAX), |s| s.interval);
+    let mut shift_intvl = stream::interval(shift_dur).fuse();
+    let growth_dur = growth.map_or(time::Duration::from_millis(u64::MAX), |g| g.interval());
+    let mut growth_intvl = stream::interval(growth_dur).fuse();
+    let churn_dur = churn.map_or(time::Duration::from_millis(u64::MAX), |c| c.interval);
+    let mut churn_intvl = stream::interval(churn_dur).fuse();
+    // the residency at the previous sample, allocated up front for the whole region
+    let previous = match dram {
+        false => Residency::new(0),
+        true => Residency::new(region.size() as usize / page_size),
+    };
+    // the first interval starts with the reporting
+    if soft_dirty {
+        if let Err(e) = clear_soft_dirty() {
+            tracing::warn!("soft-dirty: iteration {label} {e}");
+        }
+    }
+    let previous = Arc::new(sync::Mutex::new(previous));
+    let idle = idle.then(|| match IdleTracker::open() {
+        Ok(mut tracker) => {
+            // likewise marked idle at the start
+            let _ = tracker.sample(&region_ptes(region), page_size);
+            Some(Arc::new(sync::Mutex::new(tracker)))
+        }
+        Err(e) => {
+            tracing::warn!("idle-track: iteration {label} {PAGE_IDLE} unavailable: {e}");
+            None
+        }
+    });
+    let idle = idle.flatten();
+    let kpageflags = thp.then(|| match std::fs::File::open(KPAGEFLAGS) {
+        Ok(file) => Some(Arc::new(file)),
+        Err(e) => {
+            tracing::warn!("thp: iteration {label} {KPAGEFLAGS} unreadable, only the portion of the whole region by AnonHugePages of smaps shown: {e}");
+            None
+        }
+    });
+    let kpageflags = kpageflags.flatten();
+    let ratio_intvl = stream::interval(ratio_dur).fuse().then(|_| {
+        let (regions, logical, residency) = (regions.clone(), logical.clone(), residency.clone());
+        let (label, previous, idle) = (label.to_owned(), previous.clone(), idle.clone());
+        let kpageflags = kpageflags.clone();
+        // the updates of the interval up to the sample
+        let updates = CHUNK_UPDATES.get().map(ChunkUpdates::take);
+        async_std::task::spawn_blocking(move || {
+            let ptes = region_ptes(region);
+            let accessed = idle.and_then(|idle| {
+                let accessed = idle.lock().unwrap().sample(&ptes, page_size).transpose()?;
+                Some(accessed.map(|accessed| {
+                    let per_chunk = accessed_ratios(&accessed, chunk_size / page_size);
+                    let logical = logical.as_ref().map(|l| {
+                        logical_accessed(region.start_address() as usize, l, &accessed, page_size)
+                    });
+                    (per_chunk, logical)
+                }))
+            });
+            let written = soft_dirty.then(|| {
+                let written = written_pages(&ptes, chunk_size, page_size);
+                if let Err(e) = clear_soft_dirty() {
+                    tracing::warn!("soft-dirty: iteration {label} {e}");
+                }
+                written
+            });
+            if !dram {
+                return (None, written, accessed);
+            }
+            if let Some(dir) = residency {
+                if let Err(e) = residency_dump(&ptes, page_size, &dir, &label) {
+                    tracing::warn!("residency: iteration {label} {e}");
+                }
+            }
+            let ratios = chunk_ratios(&ptes, chunk_size, page_size);
+            let transitions = previous
+                .lock()
+                .unwrap()
+                .sample(&ptes, chunk_size, page_size);
+            let logical = logical.map(|l| logical_portions(region, &l, page_size));
+            let numa = numa.then(|| numa_maps(&regions));
+            let each = region_ratios(&regions, chunk_size, page_size);
+            let tiers = TIER_RANGES
+                .get()
+                .map(|tiers| tiers.portions(&ptes, chunk_size, page_size));
+            let thp = thp.then(|| match &kpageflags {
+                Some(file) => thp_ratios(file, &ptes, chunk_size).map(|ratios| {
+                    let whole = ratios.iter().sum::<f64>() / ratios.len() as f64;
+                    (whole, Some(ratios))
+                }),
+                None => anon_huge_pages(&regions)
+                    .map(|kb| ((kb << 10) as f64 / region.size() as f64, None)),
+            });
+            (
+                Some((
+                    ratios,
+                    each,
+                    logical,
+                    transitions,
+                    numa,
+                    updates,
+                    thp,
+                    tiers,
+                )),
+                written,
+                accessed,
+            )
+        })
+    });
+    pin_mut!(ratio_intvl);
+    let mut period = 0;
+    let mut total = 0;
+    let scanned = SCANNED.load(Ordering::Relaxed);
+    let mut period_scanned = scanned;
+    let written = WRITTEN.load(Ordering::Relaxed);
+    // time and number of updates when the hot and cold regions flipped
+    let mut flipped = None;
+    let threads = thread_updates();
+    let mut period_threads = threads.clone();
+    let mut period_vmstat = vmstat(&vmstat_fields);
+    let mut period_usage = usage();
+    let mut period_numa: Option<std::collections::BTreeMap<i32, usize>> = None;
+    let mut result = IterationResult {
+        label: label.to_owned(),
+        ..Default::default()
+    };
+    METRICS.lock().unwrap().label = label.to_owned();
+    let start = time::Instant::now();
+    let mut progress = budget.map(|budget| Progress::new(budget, start));
+    // the DRAM portions of the hot region of the reports and the end
+    let mut hot_dram = vec![];
+    // a line per tenth of the updates in place of the reports
+    let mut tenths = (gups_dur == time::Duration::from_millis(u64::MAX)).then_some(0);
+    tracing::info!("iteration {label} reporting worker started");
+    event("iteration_start", label, serde_json::json!({}));
+    events_flush();
+    loop {
+        select! {
+            n = count.next().fuse() => match n {
+                Some(c) => {
+                    period += c;
+                    total +=c;
+                    UPDATES.fetch_add(c, Ordering::Relaxed);
+                    if flip.is_some_and(|at| total >= at) && flipped.is_none() {
+                        FLIPPED.store(true, Ordering::Relaxed);
+                        let at = start.elapsed();
+                        tracing::info!("iteration {label} hot and cold regions flipped at {at:?} after {total} updates");
+                        event("flip", label, serde_json::json!({ "updates": total }));
+                        flipped = Some((at, total));
+                    }
+                    if let (Some(progress), Some(reported)) = (&mut progress, &mut tenths) {
+                        let tenth = (total as f64 / progress.budget as f64 * 10.0) as usize;
+                        if tenth > *reported && tenth < 10 {
+                            *reported = tenth;
+                            let (_, left) = progress.sample(start, total);
+                            report!("iteration {label} progress {}0% after {:.1?} eta {}", tenth, start.elapsed(), Progress::eta(left));
+                        }
+                    }
+                },
+                // All sender dropped
+                None => break,
+            },
+            n = gups_intvl.next().fuse() => match n {
+                Some(usage) => {
+                    let hitherto = unit.rate(total, start.elapsed().as_secs_f64());
+                    let instaneous = unit.rate(period, gups_dur.as_secs_f64());
+                    let hot = growth.map_or(String::new(), |g| {
+                        format!(" hot {} bytes", HOT_LEN.load(Ordering::Relaxed) * g.granularity)
+                    });
+                    // updates scheduled by the arrival rate so far but not issued yet
+                    let paced = pace.map_or(String::new(), |rate| {
+                        let backlog = (rate * start.elapsed().as_secs_f64()) as i64 - total as i64;
+                        format!(" rate {:.0} backlog {backlog}", period as f64 / gups_dur.as_secs_f64())
+                    });
+                    let faults = Faults::between(&period_usage, &usage);
+                    period_usage = usage;
+                    // the spread of the updates per second of the threads over the period
+                    let (spread, each) = match (thread_updates(), &mut period_threads) {
+                        (Some(now), Some(before)) => {
+                            let each = thread_rates(&now, before, gups_dur);
+                            let mut rates = each.clone();
+                            rates.sort_by(f64::total_cmp);
+                            *before = now;
+                            (format!(" threads min {:.0} median {:.0} max {:.0}", rates[0], rates[rates.len() / 2], rates[rates.len() - 1]), each)
+                        }
+                        _ => (String::new(), vec![]),
+                    };
+                    let bw = bandwidth(period, gups_dur.as_secs_f64());
+                    let done = progress.as_mut().map_or(String::new(), |progress| {
+                        let (percent, left) = progress.sample(start, total);
+                        format!(" progress {percent:.1}% eta {}", Progress::eta(left))
+                    });
+                    report!("{unit}: iteration {label} hitherto {} instaneous {}{bw}{hot}{paced}{faults}{spread}{done}", unit.show(hitherto), unit.show(instaneous));
+                    if verbose {
+                        for (t, rate) in each.iter().enumerate() {
+                            tracing::info!("iteration {label} thread {t} instaneous updates per second {rate:.0}");
+                        }
+                    }
+                    if let Some(csv) = CSV_OUT.get() {
+                        csv.gups(label, period, hitherto, instaneous, &faults);
+                    }
+                    event("gups", label, serde_json::json!({
+                        "unit": unit.to_string(),
+                        "updates": period,
+                        "hitherto": hitherto,
+                        "instantaneous": instaneous,
+                        "faults": &faults,
+                    }));
+                    METRICS.lock().unwrap().instantaneous = instaneous;
+                    result.samples.push(GupsSample {
+                        time: start.elapsed().as_secs_f64(),
+                        hitherto,
+                        instantaneous: instaneous,
+                        faults,
+                    });
+                    period = 0;
+                    if scan {
+                        let now = SCANNED.load(Ordering::Relaxed);
+                        let hitherto = (now - scanned) as f64 / start.elapsed().as_secs_f64() / 1e9;
+                        let instaneous = (now - period_scanned) as f64 / gups_dur.as_secs_f64() / 1e9;
+                        report!("scan: iteration {label} hitherto {hitherto:.3} GB/s instaneous {instaneous:.3} GB/s");
+                        period_scanned = now;
+                    }
+                    if smaps && !quiet {
+                        log_smaps(label, &regions);
+                    }
+                    if !vmstat_fields.is_empty() {
+                        let now = vmstat(&vmstat_fields);
+                        // the fields missing on this kernel are left out
+                        let deltas: String = vmstat_fields.iter().zip(now.iter().zip(&period_vmstat)).filter_map(|(field, pair)| match pair {
+                            (Some(now), Some(before)) => Some(format!(" {field} {}", *now as i64 - *before as i64)),
+                            _ => None,
+                        }).collect();
+                        report!("vmstat: iteration {label} system-wide{deltas}");
+                        period_vmstat = now;
+                    }
+                }
+                None => unreachable!(),
+            },
+            n = ratio_intvl.next().fuse() => match n {
+                Some((dram, written, accessed)) => {
+                    if let Some(written) = written {
+                        report!("iteration {label} pages written {} per {per}: {written:?}", written.iter().sum::<usize>());
+                    }
+                    match accessed {
+                        Some(Ok((ratios, logical))) => {
+                            report!("iteration {label} accessed portion per {per}: {ratios:?}");
+                            if let Some(logical) = logical {
+                                report!("iteration {label} accessed portion per region: {logical}");
+                            }
+                        }
+                        Some(Err(e)) => tracing::warn!("idle-track: iteration {label} {e}"),
+                        None => {}
+                    }
+                    if let Some(((ratios, present), each, logical, transitions, numa, updates, thp, tiers)) = dram {
+                        report!("iteration {label} dram portion per {per}: {ratios:?}");
+                        report!("iteration {label} present portion per {per}: {present:?}");
+                        if let Some(updates) = &updates {
+                            report!("iteration {label} updates per {per}: {updates:?}");
+                        }
+                        if let Some(logical) = logical {
+                            hot_dram.extend(hot_portion(&logical));
+                            report!("iteration {label} dram portion per region: {}", logical_ratios(&logical));
+                        }
+                        if let Some(transitions) = transitions {
+                            let [promoted, demoted, appeared, vanished] = [0, 1, 2, 3].map(|k| {
+                                transitions.iter().map(|t| t[k]).collect::<Vec<_>>()
+                            });
+                            let sum = |v: &[usize]| v.iter().sum::<usize>();
+                            report!(
+                                "iteration {label} pages promoted {} demoted {} newly present {} newly absent {}",
+                                sum(&promoted), sum(&demoted), sum(&appeared), sum(&vanished)
+                            );
+                            report!(
+                                "iteration {label} pages per {per} promoted {promoted:?} demoted {demoted:?} newly present {appeared:?} newly absent {vanished:?}"
+                            );
+                        }
+                        match numa {
+                            Some(Ok(numa)) => {
+                                let pages: String = numa.nodes.iter().map(|(n, p)| format!(" N{n}={p}")).collect();
+                                let kb = numa.kernel_page_kb.iter().map(usize::to_string).collect::<Vec<_>>().join(",");
+                                // the changes since the previous report, also of the nodes left since
+                                let change: String = match &period_numa {
+                                    Some(before) => {
+                                        let nodes: std::collections::BTreeSet<_> = numa.nodes.keys().chain(before.keys()).collect();
+                                        let count = |map: &std::collections::BTreeMap<i32, usize>, n| map.get(n).copied().unwrap_or(0) as i64;
+                                        nodes.into_iter().map(|n| format!(" N{n}={:+}", count(&numa.nodes, n) - count(before, n))).collect::<String>()
+                                    }
+                                    None => String::new(),
+                                };
+                                let change = match change.is_empty() {
+                                    true => String::new(),
+                                    false => format!(" change{change}"),
+                                };
+                                report!("iteration {label} numa_maps pages{pages} kernelpagesize_kB={kb}{change}");
+                                period_numa = Some(numa.nodes);
+                            }
+                            Some(Err(e)) => tracing::warn!("iteration {label} numa_maps: {e}"),
+                            None => {}
+                        }
+                        let mut sample = RatioSample::new(start.elapsed(), &ratios, &present);
+                        sample.updates = updates.unwrap_or_default();
+                        match thp {
+                            Some(Ok((whole, Some(ratios)))) => {
+                                report!("iteration {label} thp portion {whole:.4} per {per}: {ratios:?}");
+                                sample.thp = ratios;
+                            }
+                            Some(Ok((whole, None))) => report!("iteration {label} thp portion {whole:.4} by smaps"),
+                            Some(Err(e)) => tracing::warn!("thp: iteration {label} {e}"),
+                            None => {}
+                        }
+                        for (name, portions) in tiers.iter().flatten() {
+                            report!("iteration {label} {name} portion per {per}: {portions:?}");
+                        }
+                        sample.tiers = tiers.into_iter().flatten().collect();
+                        event("dram_ratio", label, serde_json::json!(&sample));
+                        result.dram_ratios.push(sample);
+                        if let Some(csv) = CSV_OUT.get() {
+                            csv.dram(label, &ratios, &present);
+                        }
+                        METRICS.lock().unwrap().dram.clone_from(&ratios);
+                        for (k, (ratios, present)) in each.iter().enumerate() {
+                            report!("iteration {label} region {k} dram portion per {per}: {ratios:?}");
+                            report!("iteration {label} region {k} present portion per {per}: {present:?}");
+                        }
+                    }
+                }
+                None => unreachable!(),
+            },
+            n = shift_intvl.next().fuse() => match n {
+                Some(_) => {
+                    let HotShift { step, len, granularity, .. } = shift.unwrap();
+                    let offset = (HOT_SHIFT.load(Ordering::Relaxed) + step) % len;
+                    HOT_SHIFT.store(offset, Ordering::Relaxed);
+                    let elapsed = start.elapsed();
+                    report!("iteration {label} hot set shifted to offset {} at {elapsed:?}", offset * granularity);
+                    event("shift", label, serde_json::json!({ "offset": offset * granularity }));
+                }
+                None => unreachable!(),
+            },
+            n = churn_intvl.next().fuse() => match n {
+                Some(_) => {
+                    let Churn { min, max, granularity, .. } = churn.unwrap();
+                    let live = rand::thread_rng().gen_range(min..=max);
+                    LIVE.store(live, Ordering::Relaxed);
+                    let elapsed = start.elapsed();
+                    report!("iteration {label} live region churned to {} bytes at {elapsed:?}", live * granularity);
+                    event("churn", label, serde_json::json!({ "live": live * granularity }));
+                }
+                None => unreachable!(),
+            },
+            n = growth_intvl.next().fuse() => match n {
+                Some(_) => {
+                    let HotGrowth { initial, max, rate, .. } = growth.unwrap();
+                    let hot = initial as f64 + rate * start.elapsed().as_secs_f64();
+                    HOT_LEN.store(hot.clamp(1.0, max as f64) as usize, Ordering::Relaxed);
+                }
+                None => unreachable!(),
+            },
+        }
+    }
+    let elapsed = start.elapsed();
+    let gups = unit.rate(total, elapsed.as_secs_f64());
+    let write_bw = (WRITTEN.load(Ordering::Relaxed) - written) as f64 / elapsed.as_secs_f64() / 1e9;
+    let swap = match overcommit {
+        true => swap_kb(),
+        false => String::new(),
+    };
+    let rates = threads.map(|before| thread_rates(&thread_updates().unwrap(), &before, elapsed));
+    // the coefficient of variation of the updates per second of the threads
+    let cv = rates.as_ref().map_or(String::new(), |rates| {
+        let mean = rates.iter().sum::<f64>() / rates.len() as f64;
+        let var = rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / rates.len() as f64;
+        format!(" cv {:.4}", var.sqrt() / mean)
+    });
+    let bw = bandwidth(total, elapsed.as_secs_f64());
+    // over the reports of the iteration, shown or not
+    let spread = result.instantaneous().map_or(String::new(), |s| {
+        format!(
+            " instaneous mean {} stddev {} min {} p5 {} p50 {} p95 {} max {}",
+            unit.show(s.mean),
+            unit.show(s.stddev),
+            unit.show(s.min),
+            unit.show(s.p5),
+            unit.show(s.p50),
+            unit.show(s.p95),
+            unit.show(s.max)
+        )
+    });
+    // the pointer chase is bound by the latency rather than the updates
+    result.hop_ns = HOP_NS.lock().unwrap().take();
+    let latency = result
+        .hop_ns
+        .map_or(String::new(), |ns| format!(" latency {ns:.1} ns per hop"));
+    tracing::info!(
+        "{unit}: iteration {label} final {} elapsed {elapsed:?}{latency}{bw}{swap}{cv}{spread}",
+        unit.show(gups)
+    );
+    // apart from the final line parsed by scripts
+    if copy {
+        tracing::info!("copy: iteration {label} write {write_bw:.3} GB/s");
+    }
+    if let Some(estimated) = progress.and_then(|p| p.estimated) {
+        tracing::info!("iteration {label} took {elapsed:.1?} of estimated {estimated:.1?}");
+    }
+    for (t, rate) in rates.iter().flatten().enumerate() {
+        tracing::info!("iteration {label} thread {t} updates per second {rate:.0}");
+    }
+    if dram {
+        let ptes = region_ptes(region);
+        let (ratios, present) = chunk_ratios(&ptes, chunk_size, page_size);
+        tracing::info!("iteration {label} final dram portion per {per}: {ratios:?}");
+        tracing::info!("iteration {label} final present portion per {per}: {present:?}");
+        let tiers = TIER_RANGES
+            .get()
+            .map(|tiers| tiers.portions(&ptes, chunk_size, page_size));
+        for (name, portions) in tiers.iter().flatten() {
+            tracing::info!("iteration {label} final {name} portion per {per}: {portions:?}");
+        }
+        let updates = CHUNK_UPDATES.get().map(ChunkUpdates::take);
+        if let Some(updates) = &updates {
+            tracing::info!("iteration {label} final updates per {per}: {updates:?}");
+        }
+        if let Some(logical) = &logical {
+            let logical = logical_portions(region, logical, page_size);
+            hot_dram.extend(hot_portion(&logical));
+            let logical = logical_ratios(&logical);
+            tracing::info!("iteration {label} final dram portion per region: {logical}");
+        }
+        result.dram_ratios.push(RatioSample {
+            updates: updates.unwrap_or_default(),
+            tiers: tiers.into_iter().flatten().collect(),
+            ..RatioSample::new(elapsed, &ratios, &present)
+        });
+        for (k, (ratios, present)) in region_ratios(&regions, chunk_size, page_size)
+            .iter()
+            .enumerate()
+        {
+            tracing::info!("iteration {label} region {k} final dram portion per {per}: {ratios:?}");
+            tracing::info!(
+                "iteration {label} region {k} final present portion per {per}: {present:?}"
+            );
+        }
+    }
+    if let Some(rate) = pace {
+        let achieved = total as f64 / elapsed.as_secs_f64();
+        tracing::info!(
+            "pace: iteration {label} achieved {achieved:.0} of {rate} updates per second"
+        );
+    }
+    if let Some((at, before)) = flipped {
+        // none over a span too short to measure, such as a flip at the very end
+        let rate = |updates, span: time::Duration| match span.is_zero() {
+            true => "none".to_owned(),
+            false => unit.show(unit.rate(updates, span.as_secs_f64())),
+        };
+        let (before, after) = (rate(before, at), rate(total - before, elapsed - at));
+        tracing::info!("flip: iteration {label} {unit} before {before} after {after}");
+    }
+    if scan {
+        let bw = (SCANNED.load(Ordering::Relaxed) - scanned) as f64 / elapsed.as_secs_f64() / 1e9;
+        tracing::info!("scan: iteration {label} final {bw:.3} GB/s");
+    }
+    (result.gups, result.elapsed) = (gups, elapsed.as_secs_f64());
+    event(
+        "iteration_end",
+        label,
+        serde_json::json!({ "unit": unit.to_string(), "updates": total, "rate": gups, "elapsed": elapsed.as_secs_f64() }),
+    );
+    events_flush();
+    let stats = IterationStats {
+        label: label.to_owned(),
+        gups,
+        elapsed: elapsed.as_secs_f64(),
+        hot_dram: (!hot_dram.is_empty())
+            .then(|| hot_dram.iter().sum::<f64>() / hot_dram.len() as f64),
+    };
+    RESULTS.lock().unwrap().push(result);
+    stats
+}
+
+/// The progress of an iteration towards its updates, the time left estimated by the rate over the
+/// recent samples so that the slow start of a run with faults does not linger in it.
+struct Progress {
+    budget: usize,
+    window: std::collections::VecDeque<(time::Instant, usize)>,
+    /// The estimated duration of the iteration once a tenth of the updates are done.
+    estimated: Option<time::Duration>,
+}
+
+impl Progress {
+    /// Samples the rate is taken over.
+    const WINDOW: usize = 10;
+
+    fn new(budget: usize, start: time::Instant) -> Self {
+        Self {
+            budget,
+            window: [(start, 0)].into(),
+            estimated: None,
+        }
+    }
+
+    /// The percentage of the updates done and the time left if any are still being done.
+    fn sample(&mut self, start: time::Instant, total: usize) -> (f64, Option<time::Duration>) {
+        let now = time::Instant::now();
+        let (since, before) = self.window[0];
+        let rate = (total - before) as f64 / (now - since).as_secs_f64();
+        let rest = self.budget.saturating_sub(total) as f64;
+        let left = (rate > 0.0).then(|| time::Duration::from_secs_f64(rest / rate));
+        self.window.push_back((now, total));
+        if self.window.len() > Self::WINDOW {
+            self.window.pop_front();
+        }
+        let done = total as f64 / self.budget as f64;
+        if self.estimated.is_none() && done >= 0.1 {
+            self.estimated = left.map(|left| now - start + left);
+        }
+        (100.0 * done, left)
+    }
+
+    fn eta(left: Option<time::Duration>) -> String {
+        left.map_or("unknown".to_owned(), |left| format!("{left:.1?}"))
+    }
+}
+
+/// The document written by `--json-out`, whose fields only change along with `schema`.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Results {
+    schema: u32,
+    /// The resolved arguments as logged on start
+    args: serde_json::Value,
+    env: Env,
+    iterations: Vec<IterationResult>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Env {
+    hostname: String,
+    kernel: String,
+    page_size: usize,
+    /// The PFNs of the DRAM if its portion is reported
+    dram_pfn_range: Option<ops::Range<u64>>,
+}
+
+/// The final and periodic reports of an iteration, the times in seconds since its start.
+#[derive(Debug, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+struct IterationResult {
+    label: String,
+    gups: f64,
+    elapsed: f64,
+    /// The average latency of a hop of the pointer chase in ns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hop_ns: Option<f64>,
+    samples: Vec<GupsSample>,
+    dram_ratios: Vec<RatioSample>,
+}
+
+impl IterationResult {
+    /// The distribution of the instantaneous gups of the reports, if any.
+    fn instantaneous(&self) -> Option<GupsSpread> {
+        let mut rates: Vec<_> = self.samples.iter().map(|s| s.instantaneous).collect();
+        rates.sort_by(f64::total_cmp);
+        let n = rates.len() as f64;
+        let mean = rates.iter().sum::<f64>() / n;
+        let var = rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+        // by the nearest rank
+        let quantile = |q: f64| rates[((q * n).ceil() as usize).clamp(1, rates.len()) - 1];
+        (!rates.is_empty()).then(|| GupsSpread {
+            mean,
+            stddev: var.sqrt(),
+            min: rates[0],
+            p5: quantile(0.05),
+            p50: quantile(0.5),
+            p95: quantile(0.95),
+            max: rates[rates.len() - 1],
+        })
+    }
+}
+
+/// The spread of the instantaneous gups of an iteration, whose dips the final gups hides.
+#[derive(Debug, serde::Serialize)]
+struct GupsSpread {
+    mean: f64,
+    stddev: f64,
+    min: f64,
+    p5: f64,
+    p50: f64,
+    p95: f64,
+    max: f64,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct GupsSample {
+    time: f64,
+    hitherto: f64,
+    instantaneous: f64,
+    #[serde(default, flatten)]
+    faults: Faults,
+}
+
+/// The page faults of the process over a report and its swap usage at the end, those unknown
+/// left out.
+#[derive(Debug, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+struct Faults {
+    minor_faults: Option<u64>,
+    major_faults: Option<u64>,
+    swap_kb: Option<usize>,
+    /// The change of the swap usage over the report.
+    swap_delta_kb: Option<i64>,
+}
+
+impl Faults {
+    fn between(
+        (before, swap_before): &(Option<libc::rusage>, Option<usize>),
+        (now, swap): &(Option<libc::rusage>, Option<usize>),
+    ) -> Self {
+        let delta =
+            |f: fn(&libc::rusage) -> i64| Some((f(now.as_ref()?) - f(before.as_ref()?)) as u64);
+        Self {
+            minor_faults: delta(|u| u.ru_minflt),
+            major_faults: delta(|u| u.ru_majflt),
+            swap_kb: *swap,
+            swap_delta_kb: swap.zip(*swap_before).map(|(n, b)| n as i64 - b as i64),
+        }
+    }
+}
+
+impl std::fmt::Display for Faults {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let (Some(minor), Some(major)) = (self.minor_faults, self.major_faults) {
+            write!(f, " faults minor {minor} major {major}")?;
+        }
+        if let (Some(swap), Some(delta)) = (self.swap_kb, self.swap_delta_kb) {
+            write!(f, " swap {swap} kB {delta:+} kB")?;
+        }
+        Ok(())
+    }
+}
+
+/// The portions per chunk as by `dram_ratio`, the DRAM one none for a chunk without present pages.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct RatioSample {
+    time: f64,
+    dram: Vec<Option<f64>>,
+    present: Vec<f64>,
+    /// The updates per chunk since the previous sample.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    updates: Vec<usize>,
+    /// The portion of the pages backed by transparent huge pages per chunk by `--thp-ratio`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    thp: Vec<f64>,
+    /// The portion of the pages per chunk on each tier of `--tiers`, present elsewhere and absent.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    tiers: std::collections::BTreeMap<String, Vec<f64>>,
+}
+
+/// The time series of `--csv-out`, each row written on its own so that a killed run leaves the
+/// rows of the reports so far.
+struct CsvOut {
+    gups: sync::Mutex<std::fs::File>,
+    dram: sync::Mutex<std::fs::File>,
+}
+
+static CSV_OUT: sync::OnceLock<CsvOut> = sync::OnceLock::new();
+
+impl CsvOut {
+    fn create(path: &path::Path) -> Result<Self> {
+        let create = |path: &path::Path, header: &str| -> Result<_> {
+            let mut file =
+                std::fs::File::create(path).map_err(|e| format!("{}: {e}", path.display()))?;
+            file.write_all(header.as_bytes())?;
+            Ok(sync::Mutex::new(file))
+        };
+        Ok(Self {
+            gups: create(
+                path,
+                "timestamp,iteration,updates,hitherto,instantaneous,minor_faults,major_faults,swap_kb\n",
+            )?,
+            dram: create(
+                &path.with_extension("dram.csv"),
+                "timestamp,iteration,chunk,dram,present\n",
+            )?,
+        })
+    }
+
+    fn gups(
+        &self,
+        label: &str,
+        updates: usize,
+        hitherto: f64,
+        instantaneous: f64,
+        faults: &Faults,
+    ) {
+        let (now, label) = (unix_time(), csv_field(label));
+        let field = |n: Option<String>| n.unwrap_or_default();
+        let faults = [
+            field(faults.minor_faults.map(|n| n.to_string())),
+            field(faults.major_faults.map(|n| n.to_string())),
+            field(faults.swap_kb.map(|n| n.to_string())),
+        ]
+        .join(",");
+        let row = format!("{now:.6},{label},{updates},{hitherto:.6},{instantaneous:.6},{faults}\n");
+        Self::write(&self.gups, &row);
+    }
+
+    /// Writes a row per chunk, leaving the DRAM portion empty for a chunk without present pages.
+    fn dram(&self, label: &str, ratios: &[f64], present: &[f64]) {
+        let (now, label) = (unix_time(), csv_field(label));
+        let mut rows = String::new();
+        for (chunk, (ratio, present)) in ratios.iter().zip(present).enumerate() {
+            let ratio = match ratio.is_nan() {
+                true => String::new(),
+                false => format!("{ratio:.6}"),
+            };
+            rows += &format!("{now:.6},{label},{chunk},{ratio},{present:.6}\n");
+        }
+        Self::write(&self.dram, &rows);
+    }
+
+    fn write(file: &sync::Mutex<std::fs::File>, rows: &str) {
+        if let Err(e) = file.lock().unwrap().write_all(rows.as_bytes()) {
+            tracing::warn!("csv: {e}");
+        }
+    }
+}
+
+/// Quotes a CSV field if need be.
+fn csv_field(s: &str) -> String {
+    match s.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", s.replace('"', "\"\"")),
+        false => s.to_owned(),
+    }
+}
+
+/// Seconds since the Unix epoch.
+fn unix_time() -> f64 {
+    let now = time::SystemTime::now().duration_since(time::UNIX_EPOCH);
+    now.unwrap_or_default().as_secs_f64()
+}
+
+/// The JSON lines of `--events-out`, buffered up to the boundaries of the iterations.
+static EVENTS: sync::OnceLock<sync::Mutex<io::BufWriter<std::fs::File>>> = sync::OnceLock::new();
+
+/// Appends an event of the given type with the timestamps, the iteration and the given fields.
+fn event(kind: &str, label: &str, fields: serde_json::Value) {
+    let Some(events) = EVENTS.get() else {
+        return;
+    };
+    let mut line = serde_json::json!({
+        "type": kind,
+        "monotonic": monotonic(),
+        "wall": unix_time(),
+        "iteration": label,
+    });
+    if let (Some(line), serde_json::Value::Object(fields)) = (line.as_object_mut(), fields) {
+        line.extend(fields);
+    }
+    if let Err(e) = writeln!(events.lock().unwrap(), "{line}") {
+        tracing::warn!("events: {e}");
+    }
+}
+
+fn events_flush() {
+    if let Some(Err(e)) = EVENTS.get().map(|events| events.lock().unwrap().flush()) {
+        tracing::warn!("events: {e}");
+    }
+}
+
+/// Seconds of `CLOCK_MONOTONIC`, the clock of the timestamps of perf and the kernel.
+fn monotonic() -> f64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as f64 + ts.tv_nsec as f64 * 1e-9
+}
+
+/// The latest reports for `--prometheus`, kept by the reporting actor.
+struct Metrics {
+    label: String,
+    instantaneous: f64,
+    dram: Vec<f64>,
+}
+
+static METRICS: sync::Mutex<Metrics> = sync::Mutex::new(Metrics {
+    label: String::new(),
+    instantaneous: 0.0,
+    dram: vec![],
+});
+/// Updates reported over all iterations so far.
+static UPDATES: AtomicUsize = AtomicUsize::new(0);
+
+/// Serves the metrics from a housekeeping thread, answering one scrape at a time so as not to
+/// compete with the workers.
+fn serve_metrics(addr: &str) -> Result<()> {
+    let listener = std::net::TcpListener::bind(addr).map_err(|e| format!("{addr}: {e}"))?;
+    tracing::info!("metrics at http://{}/metrics", listener.local_addr()?);
+    let respond = |stream: io::Result<std::net::TcpStream>| -> io::Result<()> {
+        let mut stream = stream?;
+        stream.set_read_timeout(Some(time::Duration::from_secs(1)))?;
+        let mut request = [0; 1024];
+        let n = stream.read(&mut request)?;
+        let (status, body) = match request[..n].starts_with(b"GET /metrics") {
+            true => ("200 OK", metrics()),
+            false => ("404 Not Found", String::new()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    };
+    std::thread::Builder::new()
+        .name("gups-metrics".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                if let Err(e) = respond(stream) {
+                    tracing::warn!("metrics: {e}");
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// The metrics in the Prometheus text format.
+fn metrics() -> String {
+    let Metrics {
+        label,
+        instantaneous,
+        dram,
+    } = &*METRICS.lock().unwrap();
+    let label = label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    let mut out = format!(
+        "# TYPE gups_updates_total counter\ngups_updates_total {}\n\
+         # TYPE gups_instantaneous gauge\ngups_instantaneous {instantaneous}\n\
+         # TYPE gups_iteration_info gauge\ngups_iteration_info{{label=\"{label}\"}} 1\n",
+        UPDATES.load(Ordering::Relaxed)
+    );
+    out += "# TYPE gups_dram_ratio gauge\n";
+    for (chunk, ratio) in dram.iter().enumerate() {
+        out += &format!("gups_dram_ratio{{chunk=\"{chunk}\"}} {ratio}\n");
+    }
+    out += "# TYPE gups_thread_updates_total counter\n";
+    for (t, n) in thread_updates().into_iter().flatten().enumerate() {
+        out += &format!("gups_thread_updates_total{{thread=\"{t}\"}} {n}\n");
+    }
+    out
+}
+
+/// The iterations reported so far.
+static RESULTS: sync::Mutex<Vec<IterationResult>> = sync::Mutex::new(vec![]);
+
+/// The FNV-1a hash of the resolved arguments, identifying runs of the same configuration.
+fn config_hash(args: &Args) -> Result<String> {
+    let hash = serde_json::to_string(args)?
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x100_0000_01b3)
+        });
+    Ok(format!("{hash:016x}"))
+}
+
+/// Prints the single JSON object of the run on stdout, with the final gups and elapsed seconds of
+/// each iteration under its label, e.g. `{"last":{"gups":0.1,"elapsed":9.8},...,"partial":false}`,
+/// the ns per hop of a pointer chase and the mean, standard deviation, min, max and percentiles of the instantaneous gups of its
+/// reports if any, followed by the `comparison` of the iterations in order.
+fn summary(
+    iterations: &[IterationResult],
+    stats: &[IterationStats],
+    elapsed: time::Duration,
+    config: &str,
+    partial: bool,
+) -> Result<()> {
+    let mut summary = serde_json::Map::new();
+    for it in iterations {
+        let mut value = serde_json::json!({ "gups": it.gups, "elapsed": it.elapsed });
+        if let Some(ns) = it.hop_ns {
+            value["hop_ns"] = ns.into();
+        }
+        if let Some(spread) = it.instantaneous() {
+            value["instantaneous"] = serde_json::to_value(spread)?;
+        }
+        summary.insert(it.label.clone(), value);
+    }
+    let comparison: Vec<_> = stats
+        .iter()
+        .enumerate()
+        .map(|(k, it)| {
+            let mut value = serde_json::to_value(it)?;
+            if let Some(before) = k.checked_sub(1).map(|k| &stats[k]) {
+                value["delta"] = serde_json::to_value(it.delta(before))?;
+            }
+            Ok(value)
+        })
+        .collect::<serde_json::Result<_>>()?;
+    summary.insert("comparison".into(), comparison.into());
+    summary.insert("elapsed".into(), elapsed.as_secs_f64().into());
+    summary.insert("config".into(), config.into());
+    summary.insert("partial".into(), partial.into());
+    let mut out = io::stdout().lock();
+    writeln!(out, "{}", serde_json::Value::Object(summary))?;
+    out.flush()?;
+    Ok(())
+}
+
+/// The figures of an iteration compared with the others at exit.
+#[derive(Debug, Default, serde::Serialize)]
+struct IterationStats {
+    label: String,
+    gups: f64,
+    elapsed: f64,
+    /// The mean DRAM portion of the hot region of a hotset workload over the reports and the end
+    /// by `--dram-ratio`.
+    hot_dram: Option<f64>,
+}
+
+/// The change of the figures of an iteration from the one before.
+#[derive(Debug, serde::Serialize)]
+struct IterationDelta {
+    gups: f64,
+    elapsed: f64,
+    hot_dram: Option<f64>,
+}
+
+impl IterationStats {
+    fn delta(&self, before: &Self) -> IterationDelta {
+        IterationDelta {
+            gups: self.gups - before.gups,
+            elapsed: self.elapsed - before.elapsed,
+            hot_dram: self.hot_dram.zip(before.hot_dram).map(|(a, b)| a - b),
+        }
+    }
+}
+
+/// Logs a table of the iterations with the change of each from the one before, the gups in
+/// percent, to see whether the warm-up converged.
+fn comparison(stats: &[IterationStats], unit: Unit) {
+    if stats.is_empty() {
+        return;
+    }
+    let width = stats.iter().map(|s| s.label.len()).max().unwrap().max(9);
+    let unit_name = unit.to_string();
+    tracing::info!(
+        "{:width$} {unit_name:>12} {:>8} {:>9} {:>9} {:>8} {:>8}",
+        "iteration",
+        "change",
+        "elapsed",
+        "change",
+        "hot dram",
+        "change"
+    );
+    for (k, it) in stats.iter().enumerate() {
+        let delta = k.checked_sub(1).map(|k| it.delta(&stats[k]));
+        let gups = delta.as_ref().map_or(String::new(), |d| {
+            format!("{:+.1}%", 100.0 * d.gups / stats[k - 1].gups)
+        });
+        let elapsed = delta
+            .as_ref()
+            .map_or(String::new(), |d| format!("{:+.2}s", d.elapsed));
+        let hot = it.hot_dram.map_or("-".to_owned(), |h| format!("{h:.4}"));
+        let hot_delta = delta
+            .and_then(|d| d.hot_dram)
+            .map_or(String::new(), |d| format!("{d:+.4}"));
+        tracing::info!(
+            "{:width$} {:>12} {gups:>8} {:>8.2}s {elapsed:>9} {hot:>8} {hot_delta:>8}",
+            it.label,
+            unit.show(it.gups),
+            it.elapsed
+        );
+    }
+}
+
+impl Results {
+    const SCHEMA: u32 = 1;
+
+    fn new(args: &Args) -> Result<Self> {
+        let read = |path| std::fs::read_to_string(path).map(|s| s.trim().to_owned());
+        let env = Env {
+            hostname: read("/proc/sys/kernel/hostname")?,
+            kernel: read("/proc/sys/kernel/osrelease")?,
+            page_size: *PAGE_SIZE,
+            dram_pfn_range: match args.dram_ratio.unwrap_or(u64::MAX) {
+                u64::MAX => None,
+                _ => Some(DRAM_PFN_RANGE.clone()),
+            },
+        };
+        Ok(Self {
+            schema: Self::SCHEMA,
+            args: serde_json::to_value(args)?,
+            env,
+            iterations: vec![],
+        })
+    }
+}
+
+impl RatioSample {
+    fn new(time: time::Duration, ratios: &[f64], present: &[f64]) -> Self {
+        Self {
+            time: time.as_secs_f64(),
+            dram: ratios.iter().map(|&r| (!r.is_nan()).then_some(r)).collect(),
+            present: present.to_vec(),
+            updates: vec![],
+            thp: vec![],
+            tiers: Default::default(),
+        }
+    }
+}
+
+/// The updates per second of each thread between two counts of `thread_updates`.
+fn thread_rates(now: &[usize], before: &[usize], elapsed: time::Duration) -> Vec<f64> {
+    now.iter()
+        .zip(before)
+        .map(|(now, before)| (now - before) as f64 / elapsed.as_secs_f64())
+        .collect()
+}
+
+/// The page migration counters of `/proc/vmstat` shown by `--vmstat`.
+const VMSTAT_FIELDS: [&str; 7] = [
+    "pgpromote_success",
+    "pgpromote_candidate",
+    "pgdemote_kswapd",
+    "pgdemote_direct",
+    "numa_pages_migrated",
+    "pgmigrate_success",
+    "pgmigrate_fail",
+];
+
+/// The given counters of `/proc/vmstat`, none of those missing or if it cannot be read.
+fn vmstat(fields: &[String]) -> Vec<Option<u64>> {
+    let vmstat = std::fs::read_to_string("/proc/vmstat").unwrap_or_default();
+    let counters: std::collections::BTreeMap<_, _> = vmstat
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .collect();
+    fields
+        .iter()
+        .map(|field| counters.get(field.as_str())?.trim().parse().ok())
+        .collect()
+}
+
+/// The `VmSwap` of the process to append to a report.
+fn swap_kb() -> String {
+    match status_kb("/proc/self/status", "VmSwap") {
+        Ok(kb) => format!(" swap {kb} kB"),
+        Err(e) => format!(" swap unknown: {e}"),
+    }
+}
+
+/// Value written by the `store` op, truncated to the element size.
+const STORE_VALUE: u64 = 0x5a5a_5a5a_5a5a_5a5a;
+
+/// Performs `op` on the `i`-th element of `g` bytes, which must not be `Load` or `Copy`.
+#[inline(always)]
+fn update(mem: &mut [u8], g: usize, i: usize, op: Op) {
+    #[inline(always)]
+    fn update<T>(mem: &mut [u8], i: usize, op: Op)
+    where
+        T: num_traits::WrappingAdd + num_traits::NumCast + ops::BitXor<Output = T> + Copy,
+        T: 'static,
+        u64: num_traits::AsPrimitive<T>,
+    {
+        use num_traits::AsPrimitive;
+        let ptr = mem.as_mut_ptr();
+        let len = mem.len();
+        let s = unsafe { slice::from_raw_parts_mut::<T>(ptr as _, len / mem::size_of::<T>()) };
+        s[i] = match op {
+            Op::Add => s[i].wrapping_add(&num_traits::cast(1).unwrap()),
+            Op::Xor => s[i] ^ (i as u64).as_(),
+            Op::Store => STORE_VALUE.as_(),
+            Op::Load | Op::Copy => unreachable!(),
+        };
+    }
+    match g {
+        1 => update::<u8>(mem, i, op),
+        2 => update::<u16>(mem, i, op),
+        4 => update::<u32>(mem, i, op),
+        8 => update::<u64>(mem, i, op),
+        16 => update::<u128>(mem, i, op),
+        // the parser of the granularity and `update_span` only leave these sizes
+        _ => unreachable!("update of {g} bytes"),
+    };
+}
+
+/// Like `update` but performs `op` on the `size` bytes around the `i`-th element and returns the
+/// number of bytes written. `Add`, `Xor` and `Store` update them as words of at most 8 bytes,
+/// aligned to `size` and moved back to fit into `mem` if need be. `Copy` overwrites them starting
+/// at the element from the per-thread source buffer, cut short at the end of `mem`.
+#[inline(always)]
+fn update_span(mem: &mut [u8], g: usize, i: usize, size: usize, op: Op) -> usize {
+    match op {
+        Op::Load => unreachable!(),
+        _ if op != Op::Copy && size == g => update(mem, g, i, op),
+        Op::Add | Op::Xor | Op::Store => {
+            let offset = (i * g / size * size).min(mem.len() / size * size - size);
+            match size {
+                ..=16 => update(mem, size, offset / size, op),
+                _ => (offset / 8..(offset + size) / 8).for_each(|w| update(mem, 8, w, op)),
+            }
+        }
+        Op::Copy => {
+            let offset = i * g;
+            let n = size.min(mem.len() - offset);
+            COPY_SOURCE.with(|src| {
+                let mut src = src.borrow_mut();
+                if src.len() < size {
+                    // a byte pattern distinct per thread, so copies are not all zeros
+                    let t = rayon::current_thread_index().unwrap_or(0) as u8;
+                    src.resize(size, t.wrapping_add(1));
+                }
+                unsafe {
+                    std::ptr::copy_nonoverlapping(src.as_ptr(), mem[offset..].as_mut_ptr(), n)
+                };
+            });
+            return n;
+        }
+    }
+    size
+}
+
+/// Like `load` but sums up the `size` bytes at the same position as `update_span`.
+fn load_span(mem: &[u8], g: usize, i: usize, size: usize) -> u64 {
+    if size == g {
+        return load(mem, g, i);
+    }
+    let offset = (i * g / size * size).min(mem.len() / size * size - size);
+    match size {
+        ..=16 => load(mem, size, offset / size),
+        _ => (offset / 8..(offset + size) / 8)
+            .map(|w| load(mem, 8, w))
+            .fold(0, u64::wrapping_add),
+    }
+}
+
+fn load(mem: &[u8], g: usize, i: usize) -> u64 {
+    fn load<T: num_traits::AsPrimitive<u64>>(mem: &[u8], i: usize) -> u64 {
+        let ptr = mem.as_ptr();
+        let len = mem.len();
+        let s = unsafe { slice::from_raw_parts::<T>(ptr as _, len / mem::size_of::<T>()) };
+        s[i].as_()
+    }
+    match g {
+        1 => load::<u8>(mem, i),
+        2 => load::<u16>(mem, i),
+        4 => load::<u32>(mem, i),
+        8 => load::<u64>(mem, i),
+        16 => load::<u128>(mem, i),
+        // the parser of the granularity and `load_span` only leave these sizes
+        _ => unreachable!("load of {g} bytes"),
+    }
+}
+
+/// The total `AnonHugePages` of the mappings overlapping the regions in `/proc/self/smaps`, in kB.
+fn anon_huge_pages(regions: &[pagemap::MemoryRegion]) -> Result<usize> {
+    let smaps = std::fs::read_to_string("/proc/self/smaps")?;
+    let (mut total, mut found, mut overlaps) = (0, false, false);
+    for line in smaps.lines() {
+        let key = line.split_whitespace().next().unwrap_or_default();
+        // each mapping starts with a line of its address range
+        let range = key.split_once('-').and_then(|(start, end)| {
+            Some((
+                u64::from_str_radix(start, 16).ok()?,
+                u64::from_str_radix(end, 16).ok()?,
+            ))
+        });
+        if let Some((start, end)) = range {
+            overlaps = regions
+                .iter()
+                .any(|r| r.start_address() < end && start < r.start_address() + r.size());
+            found |= overlaps;
+        } else if overlaps && key == "AnonHugePages:" {
+            let kb = line.split_whitespace().nth(1).ok_or("malformed smaps")?;
+            total += kb.parse::<usize>()?;
+        }
+    }
+    match found {
+        true => Ok(total),
+        false => Err("no mapping of the memory region in smaps".into()),
+    }
+}
+
+/// Pages per NUMA node of the mappings overlapping the memory region in `/proc/self/numa_maps`,
+/// each mapping counted in whole.
+struct NumaMaps {
+    nodes: std::collections::BTreeMap<i32, usize>,
+    /// Sizes of the pages the counts are in, more than one if the mappings differ.
+    kernel_page_kb: std::collections::BTreeSet<usize>,
+}
+
+fn numa_maps(regions: &[pagemap::MemoryRegion]) -> Result<NumaMaps> {
+    // numa_maps only tells the start of each mapping
+    let maps = std::fs::read_to_string("/proc/self/maps")?;
+    let ends: std::collections::BTreeMap<u64, u64> = maps
+        .lines()
+        .filter_map(|line| {
+            let (start, end) = line.split_whitespace().next()?.split_once('-')?;
+            Some((
+                u64::from_str_radix(start, 16).ok()?,
+                u64::from_str_radix(end, 16).ok()?,
+            ))
+        })
+        .collect();
+    let numa_maps = std::fs::read_to_string("/proc/self/numa_maps")?;
+    let (mut numa, mut found) = (
+        NumaMaps {
+            nodes: Default::default(),
+            kernel_page_kb: Default::default(),
+        },
+        false,
+    );
+    for line in numa_maps.lines() {
+        let mut fields = line.split_whitespace();
+        let start = fields.next().and_then(|s| u64::from_str_radix(s, 16).ok());
+        let Some((start, &end)) = start.and_then(|s| Some((s, ends.get(&s)?))) else {
+            continue;
+        };
+        if !regions
+            .iter()
+            .any(|r| r.start_address() < end && start < r.start_address() + r.size())
+        {
+            continue;
+        }
+        found = true;
+        // the policy such as `interleave:0-1` or `bind:0` has no `=` and is skipped with the rest
+        for (key, value) in fields.filter_map(|f| f.split_once('=')) {
+            if key == "kernelpagesize_kB" {
+                numa.kernel_page_kb.insert(value.parse()?);
+            } else if let Some(node) = key.strip_prefix('N').and_then(|n| n.parse().ok()) {
+                *numa.nodes.entry(node).or_insert(0) += value.parse::<usize>()?;
+            }
+        }
+    }
+    match found {
+        true => Ok(numa),
+        false => Err("no mapping of the memory region in numa_maps".into()),
+    }
+}
+
+/// The fields of `/proc/self/smaps_rollup` sampled by `log_smaps`.
+const SMAPS_FIELDS: [&str; 5] = ["Rss", "Pss", "Swap", "AnonHugePages", "Referenced"];
+
+/// The kB of `SMAPS_FIELDS` of the whole process and the file read, or without `smaps_rollup`
+/// those summed over the mappings overlapping the regions in `/proc/self/smaps`.
+fn smaps_rollup(regions: &[pagemap::MemoryRegion]) -> Result<([usize; 5], &'static str)> {
+    let (path, smaps) = match std::fs::read_to_string("/proc/self/smaps_rollup") {
+        Ok(rollup) => ("smaps_rollup", rollup),
+        Err(_) => ("smaps", std::fs::read_to_string("/proc/self/smaps")?),
+    };
+    let (mut fields, mut overlaps) = ([0; 5], path == "smaps_rollup");
+    for line in smaps.lines() {
+        let key = line.split_whitespace().next().unwrap_or_default();
+        let range = key.split_once('-').and_then(|(start, end)| {
+            Some((
+                u64::from_str_radix(start, 16).ok()?,
+                u64::from_str_radix(end, 16).ok()?,
+            ))
+        });
+        match (range, key.strip_suffix(':')) {
+            // the header of the rollup spans all mappings
+            (Some((start, end)), _) if path == "smaps" => {
+                overlaps = regions
+                    .iter()
+                    .any(|r| r.start_address() < end && start < r.start_address() + r.size());
+            }
+            (_, Some(key)) if overlaps => {
+                if let Some(k) = SMAPS_FIELDS.iter().position(|&f| f == key) {
+                    let kb = line.split_whitespace().nth(1).ok_or("malformed smaps")?;
+                    fields[k] += kb.parse::<usize>()?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((fields, path))
+}
+
+/// Logs the memory usage of the process as fields, to tell swapping or huge pages splitting.
+fn log_smaps(label: &str, regions: &[pagemap::MemoryRegion]) {
+    let hwm = status_kb("/proc/self/status", "VmHWM");
+    match smaps_rollup(regions).and_then(|smaps| Ok((smaps, hwm?))) {
+        Ok((([rss, pss, swap, anon_huge_pages, referenced], source), hwm)) => tracing::info!(
+            rss_kb = rss,
+            pss_kb = pss,
+            swap_kb = swap,
+            anon_huge_pages_kb = anon_huge_pages,
+            referenced_kb = referenced,
+            vm_hwm_kb = hwm,
+            source,
+            "iteration {label} memory usage"
+        ),
+        Err(e) => tracing::warn!("iteration {label} memory usage unknown: {e}"),
+    }
+}
+
+/// The mappings overlapping the memory in ascending order, clipped to its pages of `page` bytes.
+fn mem_regions(mem: &[u8], page: usize) -> Vec<pagemap::MemoryRegion> {
+    let page = page as u64;
+    let start = mem.as_ptr() as u64 / page * page;
+    let end = (mem.as_ptr() as u64 + mem.len().max(1) as u64).div_ceil(page) * page;
+    let maps = pagemap::maps(process::id() as _).unwrap();
+    let regions: Vec<_> = maps
+        .iter()
+        .map(|entry| entry.memory_region())
+        .filter(|r| r.start_address() < end && start < r.start_address() + r.size())
+        .map(|r| {
+            let (first, last) = (r.start_address(), r.start_address() + r.size());
+            pagemap::MemoryRegion::from((first.max(start), last.min(end)))
+        })
+        .collect();
+    assert!(!regions.is_empty(), "no mapping of the memory region");
+    regions
+}
+
+/// The range spanned by the adjacent mappings.
+fn mem_span(regions: &[pagemap::MemoryRegion]) -> pagemap::MemoryRegion {
+    let (first, last) = (regions[0], regions[regions.len() - 1]);
+    pagemap::MemoryRegion::from((first.start_address(), last.start_address() + last.size()))
+}
+
+/// Replaces the mapping of the memory region by a separate mapping of the same kind per region.
+/// Every other region is excluded from core dumps, which keeps the kernel from merging adjacent
+/// regions back into a single VMA.
+fn split(mem: &[u8], layout: RegionLayout, huge: Option<HugePage>, populate: bool) -> Result<()> {
+    for k in 0..layout.count {
+        map_region(mem, layout, k, huge, populate)?;
+    }
+    tracing::info!("memory regions {} of {} bytes", layout.count, layout.len);
+    Ok(())
+}
+
+/// Replaces the mapping of the `k`-th region of the memory region as by `split`.
+fn map_region(
+    mem: &[u8],
+    layout: RegionLayout,
+    k: usize,
+    huge: Option<HugePage>,
+    populate: bool,
+) -> Result<()> {
+    let mut flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED;
+    if let Some(huge) = huge {
+        flags |= libc::MAP_HUGETLB | (huge.bits() as libc::c_int) << libc::MAP_HUGE_SHIFT;
+    }
+    if populate {
+        flags |= libc::MAP_POPULATE;
+    }
+    let addr = mem.as_ptr() as usize + k * layout.len;
+    let prot = libc::PROT_READ | libc::PROT_WRITE;
+    let ptr = unsafe { libc::mmap(addr as _, layout.len, prot, flags, -1, 0) };
+    if ptr == libc::MAP_FAILED {
+        return Err(format!("mmap region {k}: {}", io::Error::last_os_error()).into());
+    }
+    if k % 2 == 1 && unsafe { libc::madvise(ptr, layout.len, libc::MADV_DONTDUMP) } != 0 {
+        return Err(format!("madvise region {k}: {}", io::Error::last_os_error()).into());
+    }
+    Ok(())
+}
+
+/// Applies the memory policy with the given nodes to the page-aligned address range.
+fn mbind(range: ops::Range<usize>, policy: Mempolicy, nodes: &[i32]) -> io::Result<()> {
+    let mask = nodemask(nodes);
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            range.start,
+            range.len(),
+            policy.mode(),
+            mask.as_ptr(),
+            // the kernel reads one bit less than the given maximum
+            mask.len() * libc::c_ulong::BITS as usize + 1,
+            0,
+        )
+    };
+    match ret {
+        ..=-1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Applies the memory policy with the given nodes to the allocations of the calling thread.
+fn set_mempolicy(policy: Mempolicy, nodes: &[i32]) -> io::Result<()> {
+    let mask = nodemask(nodes);
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_set_mempolicy,
+            policy.mode(),
+            mask.as_ptr(),
+            mask.len() * libc::c_ulong::BITS as usize + 1,
+        )
+    };
+    match ret {
+        ..=-1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Bitmask of the given nodes as taken by the memory policy syscalls.
+fn nodemask(nodes: &[i32]) -> Vec<libc::c_ulong> {
+    const BITS: usize = libc::c_ulong::BITS as usize;
+    let max = nodes.iter().max().map_or(0, |&node| node as usize + 1);
+    let mut mask = vec![0; max.div_ceil(BITS).max(1)];
+    for &node in nodes {
+        mask[node as usize / BITS] |= 1 << (node as usize % BITS);
+    }
+    mask
+}
+
+/// CPUs of the NUMA node, empty for a node of memory only.
+fn node_cpus(node: i32) -> Result<Vec<usize>> {
+    let path = format!("/sys/devices/system/node/node{node}/cpulist");
+    let list = std::fs::read_to_string(&path).map_err(|e| format!("node {node}: {path}: {e}"))?;
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|r| !r.is_empty()) {
+        let (start, end) = range.split_once('-').unwrap_or((range, range));
+        cpus.extend(start.parse::<usize>()?..=end.parse()?);
+    }
+    Ok(cpus)
+}
+
+/// Fills the memory region in stripes of `stripe` bytes, each first touched from the next node in
+/// turn so that its pages are allocated there. The stripes are aligned to addresses,
+/// the first one possibly shorter.
+fn interleave(mem: &mut [u8], nodes: &[i32], stripe: usize, init: Init) -> Result<()> {
+    let base = mem.as_ptr() as usize;
+    let head =
+        ((mem.as_ptr() as usize).next_multiple_of(stripe) - mem.as_ptr() as usize).min(mem.len());
+    let (head, rest) = mem.split_at_mut(head);
+    let mut groups: Vec<Vec<&mut [u8]>> = nodes.iter().map(|_| Vec::new()).collect();
+    let stripes = std::iter::once(head).filter(|h| !h.is_empty());
+    for (i, stripe) in stripes.chain(rest.chunks_mut(stripe)).enumerate() {
+        groups[i % nodes.len()].push(stripe);
+    }
+    touch_on_nodes(nodes.iter().copied().zip(groups).collect(), base, init)
+}
+
+/// Fills the memory of each group from a thread on its node, pinned to the CPUs of the node or,
+/// as memory only nodes have none, preferring the node.
+fn touch_on_nodes(groups: Vec<(i32, Vec<&mut [u8]>)>, base: usize, init: Init) -> Result<()> {
+    let cpus = groups
+        .iter()
+        .map(|&(node, _)| node_cpus(node))
+        .collect::<Result<Vec<_>>>()?;
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = groups
+            .into_iter()
+            .zip(cpus)
+            .map(|((node, group), cpus)| {
+                scope.spawn(move || -> std::result::Result<(), String> {
+                    match cpus.is_empty() {
+                        true => set_mempolicy(Mempolicy::Preferred, &[node])
+                            .map_err(|e| format!("set_mempolicy node {node}: {e}"))?,
+                        false => pin(&cpus).map_err(|e| format!("pin to node {node}: {e}"))?,
+                    }
+                    for stripe in group {
+                        let offset = stripe.as_ptr() as usize - base;
+                        init.fill(stripe, offset);
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .try_for_each(|t| t.join().expect("init thread panicked"))
+    })?;
+    Ok(())
+}
+
+/// The cgroup the process runs in by `--cgroup`.
+static CGROUP: sync::OnceLock<path::PathBuf> = sync::OnceLock::new();
+
+/// Fields of `memory.stat` reported for the cgroup.
+const CGROUP_STAT: &[&str] = &[
+    "anon",
+    "file",
+    "pgscan",
+    "pgsteal",
+    "pgpromote_success",
+    "pgdemote_kswapd",
+    "pgdemote_direct",
+];
+
+/// The cgroup of `--cgroup` holding the process, which moves back to its original cgroup and
+/// removes it on drop.
+struct Cgroup {
+    path: path::PathBuf,
+    origin: path::PathBuf,
+}
+impl Cgroup {
+    /// Creates the cgroup next to the current one, whose parent enables the memory controller for
+    /// it, limits its memory and moves the process into it.
+    fn new(args: &Args) -> Result<Option<Self>> {
+        let Some(name) = &args.cgroup else {
+            return Ok(None);
+        };
+        let mounts = std::fs::read_to_string("/proc/mounts")?;
+        let mount = mounts
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .find(|fields| fields.get(2) == Some(&"cgroup2"))
+            .map(|fields| path::PathBuf::from(fields[1]))
+            .ok_or("no cgroup v2 hierarchy is mounted")?;
+        let current = std::fs::read_to_string("/proc/self/cgroup")?
+            .lines()
+            .find_map(|line| line.strip_prefix("0::").map(str::to_owned))
+            .ok_or("the process is in no cgroup v2")?;
+        let origin = match current.as_str() {
+            "/" => mount,
+            _ => mount.join(current.trim_start_matches('/')),
+        };
+        let parent = match current.as_str() {
+            "/" => origin.clone(),
+            _ => origin.parent().unwrap().to_owned(),
+        };
+        let read = |path: path::PathBuf| {
+            std::fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))
+        };
+        if !read(parent.join("cgroup.controllers"))?
+            .split_whitespace()
+            .any(|c| c == "memory")
+        {
+            return Err(format!(
+                "the memory controller is unavailable in {}, either bound to cgroup v1 or not \
+                 enabled in the cgroup.subtree_control above",
+                parent.display()
+            )
+            .into());
+        }
+        if !read(parent.join("cgroup.subtree_control"))?
+            .split_whitespace()
+            .any(|c| c == "memory")
+        {
+            let control = parent.join("cgroup.subtree_control");
+            std::fs::write(&control, "+memory").map_err(|e| {
+                format!(
+                    "enabling the memory controller in {}: {e}",
+                    control.display()
+                )
+            })?;
+        }
+        let path = parent.join(name);
+        std::fs::create_dir(&path).map_err(|e| match e.kind() {
+            io::ErrorKind::PermissionDenied => format!(
+                "creating cgroup {}: {e}, run as root or delegate {} to the user",
+                path.display(),
+                parent.display()
+            ),
+            io::ErrorKind::AlreadyExists => format!(
+                "cgroup {} exists, remove it or choose another --cgroup",
+                path.display()
+            ),
+            _ => format!("creating cgroup {}: {e}", path.display()),
+        })?;
+        // removes the cgroup again if the rest fails
+        let cgroup = Cgroup { path, origin };
+        for (file, bytes) in [
+            ("memory.max", args.memory_max),
+            ("memory.high", args.memory_high),
+        ] {
+            if let Some(bytes) = bytes {
+                let file = cgroup.path.join(file);
+                std::fs::write(&file, bytes.to_string())
+                    .map_err(|e| format!("{}: {e}", file.display()))?;
+            }
+        }
+        let procs = cgroup.path.join("cgroup.procs");
+        std::fs::write(&procs, process::id().to_string()).map_err(|e| {
+            format!(
+                "moving the process to {}: {e}, which needs write access to the cgroup.procs of \
+                 both cgroups and their common ancestor",
+                procs.display()
+            )
+        })?;
+        CGROUP.set(cgroup.path.clone()).unwrap();
+        tracing::info!(
+            "cgroup {} memory.max {:?} memory.high {:?}",
+            cgroup.path.display(),
+            args.memory_max,
+            args.memory_high
+        );
+        Ok(Some(cgroup))
+    }
+}
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        let procs = self.origin.join("cgroup.procs");
+        if CGROUP.get().is_some() {
+            if let Err(e) = std::fs::write(&procs, process::id().to_string()) {
+                tracing::warn!("moving the process back to {}: {e}", procs.display());
+            }
+        }
+        if let Err(e) = std::fs::remove_dir(&self.path) {
+            tracing::warn!("removing cgroup {}: {e}", self.path.display());
+        }
+    }
+}
+
+/// The counters and sizes of `memory.stat` of the cgroup.
+fn cgroup_stat(path: &path::Path) -> Result<std::collections::BTreeMap<String, u64>> {
+    let stat = std::fs::read_to_string(path.join("memory.stat"))?;
+    Ok(stat
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(' ')?;
+            Some((name.to_owned(), value.parse().ok()?))
+        })
+        .collect())
+}
+
+/// Rejects the options `--processes` cannot honor, those signalling the workers through the state
+/// of a single process or needing a private memory region.
+fn processes_check(args: &Args) -> Result<()> {
+    let n = args.processes;
+    if n == 0 || args.thread % n != 0 {
+        return Err(format!("{} threads cannot be shared by {n} processes", args.thread).into());
+    }
+    let stateful = match &args.workload {
+        Workload::Schedule { .. } => Some("a schedule"),
+        Workload::Hotset {
+            shift_interval: Some(_),
+            ..
+        } => Some("hot set shifts"),
+        Workload::Hotset {
+            hot_grow,
+            hot_shrink,
+            ..
+        } if hot_grow.or(*hot_shrink).is_some() => Some("hot set growth"),
+        Workload::Hotset {
+            weight_schedule, ..
+        } if !weight_schedule.is_empty() => Some("a weight schedule"),
+        Workload::Hotset {
+            flip_at: Some(_), ..
+        } => Some("flipping"),
+        _ => None,
+    };
+    let stateful = stateful
+        .or(args.churn_interval.map(|_| "churn"))
+        .or(args.record_trace.as_ref().map(|_| "trace recording"))
+        .or(args.track_protect.map(|_| "tracking"))
+        .or(args.private_regions.then_some("private regions"))
+        .or(args.per_thread.then_some("per-thread reports"))
+        .or((!args.perf.is_empty()).then_some("perf counters"))
+        .or(args.soft_dirty.map(|_| "soft-dirty tracking"));
+    if let Some(feature) = stateful {
+        return Err(format!("{feature} cannot be shared by {n} processes").into());
+    }
+    if matches!(args.backing, Backing::Mmap) || args.regions > 1 || args.hugepage.is_some() {
+        return Err("processes need a shared memory region, such as --backing memfd".into());
+    }
+    Ok(())
+}
+
+/// The processes forked by `--processes` and the memory they share with the parent: the iteration
+/// the children may start, then the updates and the last iteration done by each child.
+struct Group {
+    /// Index of this process among the children, none for the parent.
+    child: Option<usize>,
+    pids: Vec<libc::pid_t>,
+    shared: &'static [AtomicUsize],
+}
+
+static GROUP: sync::OnceLock<Group> = sync::OnceLock::new();
+/// Number of iterations started by this process, the same sequence in each process.
+static ITERATIONS: AtomicUsize = AtomicUsize::new(0);
+/// Set by the SIGTERM handler of a child.
+static TERMINATED: AtomicBool = AtomicBool::new(false);
+
+fn is_child() -> bool {
+    GROUP.get().is_some_and(|g| g.child.is_some())
+}
+
+extern "C" fn terminate(_: libc::c_int) {
+    STOP.store(true, Ordering::Relaxed);
+    TERMINATED.store(true, Ordering::Relaxed);
+}
+
+/// Forks the processes of `--processes` over the shared memory region, returning the index of the
+/// child in a child.
+fn fork(args: &Args) -> Result<Option<usize>> {
+    let n = args.processes;
+    if n == 1 {
+        return Ok(None);
+    }
+    let len = (1 + 2 * n) * mem::size_of::<AtomicUsize>();
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(format!("mmap shared counters: {}", io::Error::last_os_error()).into());
+    }
+    // zeroed by the kernel and never unmapped
+    let shared = unsafe { slice::from_raw_parts(ptr as *const AtomicUsize, 1 + 2 * n) };
+    let mut pids = vec![];
+    for k in 0..n {
+        match unsafe { libc::fork() } {
+            -1 => {
+                let e = io::Error::last_os_error();
+                for &pid in &pids {
+                    unsafe { libc::kill(pid, libc::SIGTERM) };
+                }
+                let _ = GROUP.set(Group {
+                    child: None,
+                    pids,
+                    shared,
+                });
+                return Err(reap(Err(format!("fork: {e}").into())).unwrap_err());
+            }
+            0 => {
+                let handler = terminate as *const () as libc::sighandler_t;
+                unsafe { libc::signal(libc::SIGTERM, handler) };
+                let child = Some(k);
+                let _ = GROUP.set(Group {
+                    child,
+                    pids: vec![],
+                    shared,
+                });
+                return Ok(child);
+            }
+            pid => pids.push(pid),
+        }
+    }
+    tracing::info!("processes {pids:?} forked");
+    let _ = GROUP.set(Group {
+        child: None,
+        pids,
+        shared,
+    });
+    Ok(None)
+}
+
+/// Waits for the children after the parent is done, terminating them first if it failed, and
+/// fails on a child that did not exit cleanly.
+fn reap(result: Result<()>) -> Result<()> {
+    let Some(group) = GROUP.get() else {
+        return result;
+    };
+    if result.is_err() {
+        for &pid in &group.pids {
+            unsafe { libc::kill(pid, libc::SIGTERM) };
+        }
+    }
+    let mut failed = vec![];
+    for (k, &pid) in group.pids.iter().enumerate() {
+        let mut status = 0;
+        // reaped already if the relay saw it exit
+        if unsafe { libc::waitpid(pid, &mut status, 0) } == -1 {
+            continue;
+        }
+        match libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0 {
+            true => tracing::info!("process {k} pid {pid} exited"),
+            false => failed.push(format!("process {k} pid {pid} status {status:#x}")),
+        }
+    }
+    match (result, failed.is_empty()) {
+        (Err(e), _) => Err(e),
+        (Ok(()), true) => Ok(()),
+        (Ok(()), false) => Err(format!("failed {}", failed.join(", ")).into()),
+    }
+}
+
+impl Group {
+    fn generation(&self) -> &AtomicUsize {
+        &self.shared[0]
+    }
+    fn count(&self, k: usize) -> &AtomicUsize {
+        &self.shared[1 + k]
+    }
+    fn done(&self, k: usize) -> &AtomicUsize {
+        &self.shared[1 + self.shared.len() / 2 + k]
+    }
+
+    /// Runs iteration `seq` in a child once the parent starts it, adding its updates to its
+    /// counter, and exits on SIGTERM.
+    async fn iteration(
+        &self,
+        seq: usize,
+        args: Args,
+        mem: Arc<sync::RwLock<Memory>>,
+    ) -> Result<()> {
+        let k = self.child.unwrap();
+        while self.generation().load(Ordering::Acquire) < seq {
+            if TERMINATED.load(Ordering::Relaxed) {
+                process::exit(0);
+            }
+            async_std::task::sleep(time::Duration::from_millis(1)).await;
+        }
+        let stop = TERMINATED.load(Ordering::Relaxed) || INTERRUPTED.load(Ordering::Relaxed);
+        STOP.store(stop, Ordering::Relaxed);
+        let (count_tx, count_rx) = mpsc::unbounded();
+        let counts = count_rx.for_each(|n: usize| {
+            self.count(k).fetch_add(n, Ordering::Release);
+            futures::future::ready(())
+        });
+        let (res, _) = join!(
+            async_std::task::spawn_blocking(move || gups_worker(args, mem, count_tx)),
+            counts
+        );
+        self.done(k).store(seq, Ordering::Release);
+        if TERMINATED.load(Ordering::Relaxed) {
+            process::exit(0);
+        }
+        res
+    }
+
+    /// Starts iteration `seq` of the children and forwards their updates to the reporting actor of
+    /// the parent until all are done, then logs the gups of each.
+    fn relay(
+        &self,
+        label: &str,
+        seq: usize,
+        unit: Unit,
+        count_tx: mpsc::UnboundedSender<usize>,
+    ) -> Result<()> {
+        let n = self.pids.len();
+        let before: Vec<_> = (0..n)
+            .map(|k| self.count(k).load(Ordering::Acquire))
+            .collect();
+        let mut sent: usize = before.iter().sum();
+        let start = time::Instant::now();
+        self.generation().store(seq, Ordering::Release);
+        loop {
+            // the counts are final once done
+            let done = (0..n).all(|k| self.done(k).load(Ordering::Acquire) >= seq);
+            let total = (0..n).map(|k| self.count(k).load(Ordering::Acquire)).sum();
+            if total > sent {
+                count_tx.unbounded_send(total - sent)?;
+                sent = total;
+            }
+            if done {
+                break;
+            }
+            for (k, &pid) in self.pids.iter().enumerate() {
+                let mut status = 0;
+                if unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } == pid {
+                    let e = format!("process {k} pid {pid} exited with status {status:#x}");
+                    return Err(e.into());
+                }
+            }
+            std::thread::sleep(time::Duration::from_millis(1));
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        for (k, &pid) in self.pids.iter().enumerate() {
+            let updates = self.count(k).load(Ordering::Acquire) - before[k];
+            let rate = unit.show(unit.rate(updates, elapsed));
+            tracing::info!("process {k} pid {pid} {label} {unit} {rate} updates {updates}");
+        }
+        Ok(())
+    }
+}
+
+/// The CPU of each of the given number of threads and its node by `--local-alloc`, the CPUs
+/// allowed for the process taken in order and wrapping around if too few.
+fn local_cpus(threads: usize) -> Result<Vec<(usize, i32)>> {
+    let mut allowed: libc::cpu_set_t = unsafe { mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut allowed) } != 0 {
+        return Err(format!("sched_getaffinity: {}", io::Error::last_os_error()).into());
+    }
+    let mut cpus = vec![];
+    for entry in std::fs::read_dir("/sys/devices/system/node")? {
+        let name = entry?.file_name().into_string().unwrap_or_default();
+        let Some(Ok(node)) = name.strip_prefix("node").map(str::parse::<i32>) else {
+            continue;
+        };
+        for cpu in node_cpus(node)? {
+            if unsafe { libc::CPU_ISSET(cpu, &allowed) } {
+                cpus.push((cpu, node));
+            }
+        }
+    }
+    cpus.sort();
+    if cpus.is_empty() {
+        return Err("no CPU of any node is allowed for the process".into());
+    }
+    Ok((0..threads).map(|t| cpus[t % cpus.len()]).collect())
+}
+
+/// Restricts the calling thread to the given CPUs.
+fn pin(cpus: &[usize]) -> io::Result<()> {
+    let ret = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    match ret {
+        ..=-1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Number of present pages of `page_size` bytes overlapping the memory region per NUMA node, as
+/// told by the memory block holding the physical frame of each. The frames are only readable
+/// with `CAP_SYS_ADMIN`.
+fn node_pages(mem: &[u8], page_size: usize) -> Result<std::collections::BTreeMap<i32, usize>> {
+    let mut nodes = std::collections::BTreeMap::new();
+    for pfn in frames(mem, page_size)?.into_iter().flatten() {
+        let block = pfn * *PAGE_SIZE as u64 / *MEMORY_BLOCK_SIZE;
+        let node = MEMORY_BLOCK_NODES.get(&block).copied().unwrap_or(-1);
+        *nodes.entry(node).or_insert(0) += 1;
+    }
+    Ok(nodes)
+}
+
+/// Physical frame of each page of `page_size` bytes overlapping the memory, if present. Each huge
+/// page is accounted once by the entry of its first base page.
+fn frames(mem: &[u8], page_size: usize) -> Result<Vec<Option<u64>>> {
+    let base = mem.as_ptr() as usize;
+    let region = pagemap::MemoryRegion::from((
+        (base / *PAGE_SIZE * *PAGE_SIZE) as u64,
+        ((base + mem.len()).div_ceil(*PAGE_SIZE) * *PAGE_SIZE) as u64,
+    ));
+    let ptes = pagemap::PageMap::new(process::id() as _)?.pagemap_region(&region)?;
+    ptes.iter()
+        .step_by(page_size / *PAGE_SIZE)
+        .map(|pte| match pte.pfn() {
+            Ok(0) => Err("physical frames are hidden without CAP_SYS_ADMIN".into()),
+            Ok(pfn) => Ok(Some(pfn)),
+            Err(_) => Ok(None),
+        })
+        .collect()
+}
+
+/// Migration flag of `move_pages` for pages mapped only by this process.
+const MPOL_MF_MOVE: libc::c_int = 1 << 1;
+
+/// Moves the given pages to `node`, or only queries their nodes if `None`, returning the node or
+/// negative errno of each page.
+fn move_pages(pages: &[*mut libc::c_void], node: Option<i32>) -> io::Result<Vec<i32>> {
+    let nodes = node.map(|node| vec![node; pages.len()]);
+    let mut status = vec![0; pages.len()];
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_move_pages,
+            0,
+            pages.len(),
+            pages.as_ptr(),
+            nodes.as_ref().map_or(std::ptr::null(), |n| n.as_ptr()),
+            status.as_mut_ptr(),
+            MPOL_MF_MOVE,
+        )
+    };
+    match ret {
+        ..=-1 => Err(io::Error::last_os_error()),
+        _ => Ok(status),
+    }
+}
+
+/// Byte ranges of the hot regions of the hotset workload in ascending order, or `None` for other
+/// workloads.
+fn hot_ranges(args: &Args) -> Result<Option<Vec<ops::Range<usize>>>> {
+    let Workload::Hotset {
+        hot,
+        weight,
+        offsets,
+        reverse,
+        regions,
+        ..
+    } = &args.workload
+    else {
+        return Ok(None);
+    };
+    if !regions.is_empty() {
+        return Ok(None);
+    }
+    let (len, g) = (args.region_len(), args.granularity.unit());
+    let top = len / g * g;
+    let mut hots: Vec<_> = hotset_layout(len, g, hot, weight, offsets)?
+        .into_iter()
+        .map(|(r, _)| match reverse {
+            true => top - r.end..top - r.start,
+            false => r,
+        })
+        .collect();
+    hots.sort_by_key(|r| r.start);
+    Ok(Some(hots))
+}
+
+/// The gaps of `0..len` between the ascending disjoint ranges.
+fn complement(ranges: &[ops::Range<usize>], len: usize) -> Vec<ops::Range<usize>> {
+    let mut gaps = vec![];
+    let mut start = 0;
+    for r in ranges.iter().chain([&(len..len)]) {
+        if start < r.start {
+            gaps.push(start..r.start);
+        }
+        start = r.end;
+    }
+    gaps
+}
+
+/// First touches the hot regions from `hot=<node>` and the rest of the memory region from
+/// `cold=<node>`, or from this thread for an omitted node, logging the DRAM ratio and pages per
+/// node of each afterwards. Pages straddling a hot region and the rest are hot.
+fn first_touch(args: &Args, mem: &mut [u8]) -> Result<()> {
+    let Some(touch) = args.first_touch else {
+        return Ok(());
+    };
+    let hots = match hot_ranges(args)? {
+        Some(hots) => hots,
+        None => return Err(format!("{:?} has no hot region to touch", args.workload).into()),
+    };
+    // widen to whole pages, merging the hot regions that come to share a page
+    let (base, page) = (mem.as_ptr() as usize, args.page_size());
+    let mut pages: Vec<ops::Range<usize>> = vec![];
+    for r in hots {
+        let start = ((base + r.start) / page * page).saturating_sub(base);
+        let end = ((base + r.end).div_ceil(page) * page - base).min(mem.len());
+        match pages.last_mut() {
+            Some(last) if last.end >= start => last.end = last.end.max(end),
+            _ => pages.push(start..end),
+        }
+    }
+    let colds = complement(&pages, mem.len());
+    let mut ranges: Vec<_> = pages
+        .iter()
+        .map(|r| (true, r.clone()))
+        .chain(colds.iter().map(|r| (false, r.clone())))
+        .collect();
+    ranges.sort_by_key(|(_, r)| r.start);
+    let (mut hot, mut cold) = (vec![], vec![]);
+    let (mut rest, mut offset) = (&mut *mem, 0);
+    for (is_hot, r) in ranges {
+        let (head, tail) = std::mem::take(&mut rest).split_at_mut(r.end - offset);
+        (rest, offset) = (tail, r.end);
+        match is_hot {
+            true => hot.push(head),
+            false => cold.push(head),
+        }
+    }
+    let start = time::Instant::now();
+    let mut groups = vec![];
+    for (node, slices) in [(touch.hot, hot), (touch.cold, cold)] {
+        match node {
+            Some(node) => groups.push((node, slices)),
+            None => {
+                for s in slices {
+                    let offset = s.as_ptr() as usize - base;
+                    args.init.fill(s, offset);
+                }
+            }
+        }
+    }
+    touch_on_nodes(groups, base, args.init)?;
+    tracing::info!(
+        "first touch {:?} {} bytes in {:?}",
+        args.init,
+        mem.len(),
+        start.elapsed()
+    );
+    for (name, ranges, node) in [("hot", pages, touch.hot), ("cold", colds, touch.cold)] {
+        let (mut dram, mut total) = (0, 0);
+        let mut nodes = std::collections::BTreeMap::new();
+        for r in ranges {
+            let frames = frames(&mem[r.clone()], page)?;
+            total += frames.len();
+            dram += frames
+                .iter()
+                .flatten()
+                .filter(|pfn| DRAM_PFN_RANGE.contains(pfn))
+                .count();
+            for (n, count) in node_pages(&mem[r], page)? {
+                *nodes.entry(n).or_insert(0) += count;
+            }
+        }
+        tracing::info!(
+            "first touch {name} region from node {node:?} dram ratio {:.3} pages per node {nodes:?}",
+            dram as f64 / total.max(1) as f64
+        );
+    }
+    Ok(())
+}
+
+/// The address ranges locked by `--mlock`, unlocked when dropped.
+struct Locked(Vec<ops::Range<usize>>);
+impl Drop for Locked {
+    fn drop(&mut self) {
+        for r in &self.0 {
+            if unsafe { libc::munlock(r.start as _, r.len()) } != 0 {
+                tracing::warn!("munlock {r:x?}: {}", io::Error::last_os_error());
+            }
+        }
+    }
+}
+
+/// Locks the ranges of the memory region chosen by `--mlock` into memory until the returned guard
+/// is dropped.
+fn mlock(args: &Args, mem: &[u8]) -> Result<Locked> {
+    let whole = 0..mem.len();
+    let ranges = match (args.mlock, hot_ranges(args)?) {
+        (Mlock::None, _) => return Ok(Locked(vec![])),
+        (Mlock::All, _) => vec![whole],
+        (Mlock::Hot, Some(hots)) => hots,
+        (Mlock::Cold, Some(hots)) => complement(&hots, mem.len()),
+        (mode, None) => {
+            return Err(
+                format!("{:?} has no hot region for --mlock {mode:?}", args.workload).into(),
+            )
+        }
+    };
+    let base = mem.as_ptr() as usize;
+    let ranges: Vec<_> = ranges
+        .iter()
+        .map(|r| base + r.start..base + r.end)
+        .collect();
+    for (i, r) in ranges.iter().enumerate() {
+        if unsafe { libc::mlock(r.start as _, r.len()) } == 0 {
+            continue;
+        }
+        let e = io::Error::last_os_error();
+        for r in &ranges[..i] {
+            unsafe { libc::munlock(r.start as _, r.len()) };
+        }
+        let mut limit: libc::rlimit = unsafe { std::mem::zeroed() };
+        unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) };
+        let limit = match limit.rlim_cur {
+            libc::RLIM_INFINITY => "unlimited".to_string(),
+            bytes => format!("{bytes} bytes"),
+        };
+        let size: usize = ranges.iter().map(|r| r.len()).sum();
+        return Err(format!("mlock {size} bytes with RLIMIT_MEMLOCK {limit}: {e}").into());
+    }
+    let bytes: usize = ranges.iter().map(|r| r.len()).sum();
+    tracing::info!("mlock {:?} {bytes} bytes locked", args.mlock);
+    Ok(Locked(ranges))
+}
+
+/// Advises the kernel of the use of the hot and cold regions by `--madvise`, logging the result of
+/// each call after `when`. The ranges shrink to whole pages, so that the hint for one region
+/// never applies to the other.
+fn madvise(args: &Args, mem: &[u8], when: &str) -> Result<()> {
+    let Some(Madvise { hot, cold }) = args.madvise else {
+        return Ok(());
     };
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(thread)
-        .thread_name(|i| format!("gups-rayon-{}", i))
-        .build_scoped(
-            |thread| {
-                do_init();
-                tracing::info!("thread {:?} started", thread.index());
-                thread.run();
-            },
-            |pool| {
-                pool.install(do_work);
-            },
-        )?;
+    let hots = match hot_ranges(args)? {
+        Some(hots) => hots,
+        None => return Err(format!("{:?} has no hot region to advise", args.workload).into()),
+    };
+    let colds = complement(&hots, mem.len());
+    let (base, page) = (mem.as_ptr() as usize, args.page_size());
+    for (name, ranges, hint) in [("hot", hots, hot), ("cold", colds, cold)] {
+        let Some(hint) = hint else { continue };
+        for r in ranges {
+            let (start, end) = (
+                (base + r.start).next_multiple_of(page),
+                (base + r.end) / page * page,
+            );
+            if start >= end {
+                tracing::info!("madvise {when} {name} {hint:?} bytes {r:?} spans no whole page");
+                continue;
+            }
+            match unsafe { libc::madvise(start as _, end - start, hint.advice()) } {
+                0 => tracing::info!(
+                    "madvise {when} {name} {hint:?} bytes {:?} done",
+                    start - base..end - base
+                ),
+                _ => tracing::warn!(
+                    "madvise {when} {name} {hint:?} bytes {:?} failed: {}",
+                    start - base..end - base,
+                    io::Error::last_os_error()
+                ),
+            }
+        }
+    }
     Ok(())
 }
 
-async fn reporting_actor(
-    label: &str,
-    mut count: mpsc::UnboundedReceiver<usize>,
-    gups_dur: time::Duration,
-    ratio_dur: time::Duration,
-    region: pagemap::MemoryRegion,
-) {
-    let region = region.clone();
-    let chunk_size = 1usize << 30;
-    let mut gups_intvl = stream::interval(gups_dur).fuse();
-    let ratio_intvl = stream::interval(ratio_dur)
-        .fuse()
-        .then(|_| async_std::task::spawn_blocking(move || dram_ratio(region, chunk_size)));
-    pin_mut!(ratio_intvl);
-    let mut period = 0;
-    let mut total = 0;
-    let start = time::Instant::now();
-    tracing::info!("iteration {label} reporting worker started");
-    loop {
-        select! {
-            n = count.next().fuse() => match n {
-                Some(c) => {
-                    period += c;
-                    total +=c;
-                },
-                // All sender dropped
-                None => break,
-            },
-            n = gups_intvl.next().fuse() => match n {
-                Some(_) => {
-                    let hitherto = total as f64 / start.elapsed().as_secs_f64() / chunk_size as f64;
-                    let instaneous = period as f64 / gups_dur.as_secs_f64() / chunk_size as f64;
-                    tracing::info!("GUPS: iteration {label} hitherto {hitherto:.6} instaneous {instaneous:.6}");
-                    period = 0;
+/// Places the hot regions on `--hot-node` and the rest of the memory region on `--cold-node`,
+/// logging the resulting number of pages per node. Pages that fail to move only warrant a warning.
+fn place(args: &Args, mem: &[u8]) -> Result<()> {
+    if args.hot_node.is_none() && args.cold_node.is_none() {
+        return Ok(());
+    }
+    let hots = match hot_ranges(args)? {
+        Some(hots) => hots,
+        None if args.hot_node.is_some() => {
+            return Err(format!("{:?} has no hot region to place", args.workload).into())
+        }
+        None => vec![],
+    };
+    let colds = complement(&hots, mem.len());
+    let page = *PAGE_SIZE;
+    let base = mem.as_ptr() as usize;
+    for (name, ranges, node) in [
+        ("hot", hots, args.hot_node),
+        ("cold", colds, args.cold_node),
+    ] {
+        let Some(node) = node else { continue };
+        // every page overlapping the ranges
+        let pages: Vec<_> = ranges
+            .iter()
+            .flat_map(|r| (base + r.start) / page..(base + r.end).div_ceil(page))
+            .map(|p| (p * page) as *mut libc::c_void)
+            .collect();
+        if let Err(e) = move_pages(&pages, Some(node)) {
+            tracing::warn!("failed to bind {name} region to node {node}: {e}");
+        }
+        let mut nodes = std::collections::BTreeMap::new();
+        for status in move_pages(&pages, None)? {
+            *nodes.entry(status).or_insert(0) += 1;
+        }
+        let present = ranges
+            .iter()
+            .map(|r| {
+                let region = pagemap::MemoryRegion::from((
+                    ((base + r.start) / page * page) as u64,
+                    ((base + r.end).div_ceil(page) * page) as u64,
+                ));
+                let ptes = pagemap::PageMap::new(process::id() as _)?.pagemap_region(&region)?;
+                Ok(ptes.iter().filter(|e| e.present()).count())
+            })
+            .sum::<std::result::Result<usize, pagemap::PageMapError>>()?;
+        let on_node = nodes.get(&node).copied().unwrap_or(0);
+        tracing::info!(
+            "{name} region {} pages, {present} present, per node {nodes:?}",
+            pages.len()
+        );
+        if on_node < pages.len() {
+            tracing::warn!(
+                "only {on_node} of {} {name} pages placed on node {node}",
+                pages.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Moves the pages of the byte ranges of `--place` to their nodes and reads their nodes back,
+/// logging for each range how many pages landed on the requested node. Pages that fail to move,
+/// say for want of memory on the node, are counted per error rather than fatal.
+fn place_ranges(args: &Args, mem: &[u8]) -> Result<()> {
+    let Some(Placement(ranges)) = &args.place else {
+        return Ok(());
+    };
+    let ranges = ranges
+        .iter()
+        .map(|&(start, end, node)| match end.unwrap_or(mem.len()) {
+            end if start < end && end <= mem.len() => Ok((start..end, node)),
+            end => Err(format!(
+                "invalid range {start}..{end} of {} bytes",
+                mem.len()
+            )),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut sorted: Vec<_> = ranges.iter().map(|(r, _)| r.clone()).collect();
+    sorted.sort_by_key(|r| r.start);
+    if let Some(w) = sorted.windows(2).find(|w| w[0].end > w[1].start) {
+        return Err(format!("overlapping ranges {:?} and {:?}", w[0], w[1]).into());
+    }
+    let (base, page) = (mem.as_ptr() as usize, *PAGE_SIZE);
+    for (r, node) in ranges {
+        // every page overlapping the range
+        let pages: Vec<_> = ((base + r.start) / page..(base + r.end).div_ceil(page))
+            .map(|p| (p * page) as *mut libc::c_void)
+            .collect();
+        let start = time::Instant::now();
+        let mut failed = std::collections::BTreeMap::new();
+        match move_pages(&pages, Some(node)) {
+            Ok(status) => {
+                for errno in status.into_iter().filter(|&s| s < 0).map(|s| -s) {
+                    *failed
+                        .entry(io::Error::from_raw_os_error(errno).to_string())
+                        .or_insert(0) += 1;
                 }
-                None => unreachable!(),
-            },
-            n = ratio_intvl.next().fuse() => match n {
-                Some(ratios) => {
-                    tracing::info!("iteration {label} dram portion per gb: {ratios:?}");
+            }
+            Err(e) => {
+                tracing::warn!("failed to move {r:?} to node {node}: {e}");
+                failed.insert(e.to_string(), pages.len());
+            }
+        }
+        let elapsed = start.elapsed();
+        let mut nodes = std::collections::BTreeMap::new();
+        for status in move_pages(&pages, None)? {
+            *nodes.entry(status).or_insert(0) += 1;
+        }
+        let on_node = nodes.get(&node).copied().unwrap_or(0);
+        tracing::info!(
+            "place {r:?} on node {node} in {elapsed:?}: {on_node} of {} pages placed, failed {failed:?}, per node {nodes:?}",
+            pages.len()
+        );
+        if on_node < pages.len() {
+            tracing::warn!(
+                "only {on_node} of {} pages of {r:?} placed on node {node}",
+                pages.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Like `dram_ratio` for each of several mappings of the memory region, none for a single one.
+fn region_ratios(
+    regions: &[pagemap::MemoryRegion],
+    chunk_size: usize,
+    page_size: usize,
+) -> Vec<Ratios> {
+    match regions.len() {
+        1 => vec![],
+        _ => regions
+            .iter()
+            .map(|&r| dram_ratio(r, chunk_size, page_size))
+            .collect(),
+    }
+}
+
+/// Portion of the present pages of `page_size` bytes in each chunk of the region that reside in
+/// the DRAM, NaN for a chunk without any, along with the portion of the pages that are present.
+/// Each huge page is accounted once by the entry of its first base page.
+fn dram_ratio(region: pagemap::MemoryRegion, chunk_size: usize, page_size: usize) -> Ratios {
+    chunk_ratios(&region_ptes(region), chunk_size, page_size)
+}
+
+/// The page table entries of the base pages of the region.
+fn region_ptes(region: pagemap::MemoryRegion) -> Vec<pagemap::PageMapEntry> {
+    pagemap::PageMap::new(process::id() as _)
+        .unwrap()
+        .pagemap_region(&region)
+        .unwrap()
+}
+
+/// Like `dram_ratio` but of the entries read already.
+fn chunk_ratios(ptes: &[pagemap::PageMapEntry], chunk_size: usize, page_size: usize) -> Ratios {
+    ptes.chunks(chunk_size / *PAGE_SIZE)
+        .map(|ptes| {
+            let pages = ptes.iter().step_by(page_size / *PAGE_SIZE);
+            let (dram, present, n) = pages.fold((0, 0, 0), |(dram, present, n), e| {
+                let d = e.present() && DRAM_PFN_RANGE.contains(&e.pfn().unwrap());
+                (dram + d as usize, present + e.present() as usize, n + 1)
+            });
+            (dram as f64 / present as f64, present as f64 / n as f64)
+        })
+        .unzip()
+}
+
+/// The frames of the tiers of `--tiers`.
+struct TierRanges {
+    names: Vec<String>,
+    /// The sorted ranges of frames, each with its tier.
+    ranges: Vec<(ops::Range<u64>, usize)>,
+}
+
+static TIER_RANGES: sync::OnceLock<TierRanges> = sync::OnceLock::new();
+
+impl TierRanges {
+    /// Merges the adjacent memory blocks of the nodes of each tier into ranges.
+    fn new(Tiers(tiers): &Tiers) -> Result<Self> {
+        let pages = *MEMORY_BLOCK_SIZE / *PAGE_SIZE as u64;
+        let mut ranges: Vec<(ops::Range<u64>, usize)> = vec![];
+        for (&block, node) in MEMORY_BLOCK_NODES.iter() {
+            let Some(k) = tiers.iter().position(|(_, nodes)| nodes.contains(node)) else {
+                continue;
+            };
+            let pfns = block * pages..(block + 1) * pages;
+            match ranges.last_mut() {
+                Some((last, tier)) if *tier == k && last.end == pfns.start => last.end = pfns.end,
+                _ => ranges.push((pfns, k)),
+            }
+        }
+        for (k, (name, nodes)) in tiers.iter().enumerate() {
+            let of: Vec<_> = ranges
+                .iter()
+                .filter(|(_, t)| *t == k)
+                .map(|(r, _)| r)
+                .collect();
+            if of.is_empty() {
+                return Err(format!("tier {name}: no memory blocks on nodes {nodes:?}").into());
+            }
+            tracing::info!("tier {name} nodes {nodes:?} pfn ranges {of:?}");
+        }
+        Ok(Self {
+            names: tiers.iter().map(|(name, _)| name.clone()).collect(),
+            ranges,
+        })
+    }
+
+    fn tier(&self, pfn: u64) -> Option<usize> {
+        let k = self.ranges.partition_point(|(r, _)| r.end <= pfn);
+        let (r, tier) = self.ranges.get(k)?;
+        r.contains(&pfn).then_some(*tier)
+    }
+
+    /// The portion of the pages of `page_size` bytes in each chunk on each tier, present elsewhere
+    /// and not present, classified in a single pass.
+    fn portions(
+        &self,
+        ptes: &[pagemap::PageMapEntry],
+        chunk_size: usize,
+        page_size: usize,
+    ) -> Vec<(String, Vec<f64>)> {
+        let n = self.names.len();
+        let mut portions = vec![vec![]; n + 2];
+        for ptes in ptes.chunks(chunk_size / *PAGE_SIZE) {
+            let mut counts = vec![0; n + 2];
+            let pages = ptes.iter().step_by(page_size / *PAGE_SIZE);
+            for e in pages.clone() {
+                let k = match (e.present(), e.pfn()) {
+                    (true, Ok(pfn)) => self.tier(pfn).unwrap_or(n),
+                    (true, Err(_)) => n,
+                    (false, _) => n + 1,
+                };
+                counts[k] += 1;
+            }
+            let total = pages.count();
+            for (portions, count) in portions.iter_mut().zip(counts) {
+                portions.push(count as f64 / total as f64);
+            }
+        }
+        let names = self.names.iter().cloned();
+        names
+            .chain(["elsewhere".to_owned(), "absent".to_owned()])
+            .zip(portions)
+            .collect()
+    }
+}
+
+/// The present and in-DRAM bits of each page at the previous sample, to count the pages changing
+/// tier in between.
+struct Residency {
+    bits: Vec<u64>,
+    sampled: bool,
+}
+
+/// The pages promoted to DRAM, demoted from it, newly present and newly absent in each chunk.
+type Transitions = Vec<[usize; 4]>;
+
+impl Residency {
+    fn new(pages: usize) -> Self {
+        Self {
+            bits: vec![0; pages.div_ceil(32)],
+            sampled: false,
+        }
+    }
+
+    /// Counts the transitions since the previous sample, none at the first, and retains this one.
+    fn sample(
+        &mut self,
+        ptes: &[pagemap::PageMapEntry],
+        chunk_size: usize,
+        page_size: usize,
+    ) -> Option<Transitions> {
+        let step = page_size / *PAGE_SIZE;
+        let mut transitions = vec![[0; 4]; ptes.len().div_ceil(chunk_size / *PAGE_SIZE)];
+        for (i, e) in ptes
+            .iter()
+            .step_by(step)
+            .enumerate()
+            .take(self.bits.len() * 32)
+        {
+            let present = e.present();
+            let dram = present && DRAM_PFN_RANGE.contains(&e.pfn().unwrap());
+            let (word, shift) = (&mut self.bits[i / 32], 2 * (i % 32));
+            let (was_present, was_dram) = (*word >> shift & 1 == 1, *word >> shift & 2 == 2);
+            *word = *word & !(3 << shift) | (present as u64 | (dram as u64) << 1) << shift;
+            let t = &mut transitions[i * page_size / chunk_size];
+            match (was_present, present) {
+                (true, true) => {
+                    t[0] += (!was_dram && dram) as usize;
+                    t[1] += (was_dram && !dram) as usize;
                 }
-                None => unreachable!(),
-            },
+                (false, true) => t[2] += 1,
+                (true, false) => t[3] += 1,
+                (false, false) => {}
+            }
         }
+        mem::replace(&mut self.sampled, true).then_some(transitions)
     }
-    let elapsed = start.elapsed();
-    let gups = total as f64 / elapsed.as_secs_f64() / chunk_size as f64;
-    tracing::info!("GUPS: iteration {label} final {gups:.6} elapsed {elapsed:?}");
 }
 
-fn update(mem: &mut [u8], g: usize, i: usize) {
-    fn update<T: num_traits::WrappingAdd + num_traits::NumCast>(mem: &mut [u8], i: usize) {
-        let ptr = mem.as_mut_ptr();
-        let len = mem.len();
-        let s = unsafe { slice::from_raw_parts_mut::<T>(ptr as _, len / mem::size_of::<T>()) };
-        s[i] = s[i].wrapping_add(&num_traits::cast(1).unwrap());
+/// Number of pages of `page_size` bytes written since the soft-dirty bits were cleared per chunk.
+fn written_pages(
+    ptes: &[pagemap::PageMapEntry],
+    chunk_size: usize,
+    page_size: usize,
+) -> Vec<usize> {
+    ptes.chunks(chunk_size / *PAGE_SIZE)
+        .map(|ptes| {
+            let pages = ptes.iter().step_by(page_size / *PAGE_SIZE);
+            pages.filter(|e| e.present() && e.soft_dirty()).count()
+        })
+        .collect()
+}
+
+/// Fails unless the kernel sets the soft-dirty bit of a page just written, which it does not without
+/// `CONFIG_MEM_SOFT_DIRTY`.
+fn soft_dirty_check() -> Result<()> {
+    let mut page = vec![0u8; 2 * *PAGE_SIZE];
+    let base = page.as_ptr() as usize;
+    let addr = base.div_ceil(*PAGE_SIZE) * *PAGE_SIZE;
+    clear_soft_dirty()?;
+    page[addr - base] = 1;
+    let region = pagemap::MemoryRegion::from((addr as u64, (addr + *PAGE_SIZE) as u64));
+    let ptes = pagemap::PageMap::new(process::id() as _)?.pagemap_region(&region)?;
+    match ptes[0].soft_dirty() {
+        true => Ok(()),
+        false => Err("soft-dirty bits are not tracked by this kernel".into()),
     }
-    match g {
-        1 => update::<u8>(mem, i),
-        2 => update::<u16>(mem, i),
-        4 => update::<u32>(mem, i),
-        8 => update::<u64>(mem, i),
-        16 => update::<u128>(mem, i),
-        _ => unimplemented!(),
+}
+
+/// Clears the soft-dirty bits of all pages of the process.
+fn clear_soft_dirty() -> io::Result<()> {
+    std::fs::write("/proc/self/clear_refs", "4")
+}
+
+/// The page idle bitmap of `--idle-track`, a bit per frame set while the page is not accessed.
+const PAGE_IDLE: &str = "/sys/kernel/mm/page_idle/bitmap";
+
+/// Tells the pages accessed since they were marked idle at the previous sample.
+struct IdleTracker {
+    bitmap: std::fs::File,
+    marked: bool,
+}
+
+impl IdleTracker {
+    /// Fails without `CONFIG_IDLE_PAGE_TRACKING`.
+    fn open() -> io::Result<Self> {
+        let bitmap = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(PAGE_IDLE)?;
+        Ok(Self {
+            bitmap,
+            marked: false,
+        })
+    }
+
+    /// Whether each page of `page_size` bytes was accessed since the previous sample if present,
+    /// none at the first, then marks the present pages idle. The bitmap is read and written in
+    /// runs of consecutive words of 64 frames.
+    fn sample(
+        &mut self,
+        ptes: &[pagemap::PageMapEntry],
+        page_size: usize,
+    ) -> Result<Option<Vec<Option<bool>>>> {
+        use std::os::unix::fs::FileExt;
+        let pfns = ptes
+            .iter()
+            .step_by(page_size / *PAGE_SIZE)
+            .map(|e| match (e.present(), e.pfn()) {
+                (true, Ok(0)) => Err("physical frames are hidden without CAP_SYS_ADMIN".into()),
+                (true, Ok(pfn)) => Ok(Some(pfn)),
+                _ => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // the frames of the pages in each word of the bitmap
+        let mut words = std::collections::BTreeMap::<u64, u64>::new();
+        for pfn in pfns.iter().flatten() {
+            *words.entry(pfn / 64).or_default() |= 1 << (pfn % 64);
+        }
+        let words: Vec<_> = words.into_iter().collect();
+        let mut runs = vec![];
+        for &(word, _) in &words {
+            match runs.last_mut() {
+                Some((first, run)) if *first + (*run as u64) == word => *run += 1,
+                _ => runs.push((word, 1usize)),
+            }
+        }
+        let mut idle = std::collections::BTreeMap::new();
+        let mut next = 0;
+        for &(first, run) in &runs {
+            let mut buf = vec![0; run * 8];
+            if self.marked {
+                self.bitmap.read_exact_at(&mut buf, first * 8)?;
+                for (k, bits) in buf.chunks(8).enumerate() {
+                    idle.insert(
+                        first + k as u64,
+                        u64::from_ne_bytes(bits.try_into().unwrap()),
+                    );
+                }
+            }
+            // only the bits set are marked idle
+            for (k, bits) in buf.chunks_mut(8).enumerate() {
+                bits.copy_from_slice(&words[next + k].1.to_ne_bytes());
+            }
+            self.bitmap.write_all_at(&buf, first * 8)?;
+            next += run;
+        }
+        Ok(mem::replace(&mut self.marked, true).then(|| {
+            pfns.iter()
+                .map(|pfn| pfn.map(|pfn| idle[&(pfn / 64)] >> (pfn % 64) & 1 == 0))
+                .collect()
+        }))
+    }
+}
+
+const KPAGEFLAGS: &str = "/proc/kpageflags";
+/// The flag of each base page of a transparent huge page in `/proc/kpageflags`.
+const KPF_THP: u64 = 1 << 22;
+
+/// The portion of the base pages in each chunk backed by transparent huge pages, their flags read
+/// in runs of consecutive frames.
+fn thp_ratios(
+    kpageflags: &std::fs::File,
+    ptes: &[pagemap::PageMapEntry],
+    chunk_size: usize,
+) -> Result<Vec<f64>> {
+    use std::os::unix::fs::FileExt;
+    let pfns = ptes
+        .iter()
+        .map(|e| match (e.present(), e.pfn()) {
+            (true, Ok(0)) => Err("physical frames are hidden without CAP_SYS_ADMIN".into()),
+            (true, Ok(pfn)) => Ok(Some(pfn)),
+            _ => Ok(None),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut huge = vec![false; pfns.len()];
+    let mut k = 0;
+    while k < pfns.len() {
+        let Some(first) = pfns[k] else {
+            k += 1;
+            continue;
+        };
+        let run = pfns[k..]
+            .iter()
+            .zip(first..)
+            .take_while(|&(pfn, next)| *pfn == Some(next))
+            .count();
+        let mut buf = vec![0; run * 8];
+        kpageflags.read_exact_at(&mut buf, first * 8)?;
+        for (i, flags) in buf.chunks(8).enumerate() {
+            huge[k + i] = u64::from_ne_bytes(flags.try_into().unwrap()) & KPF_THP != 0;
+        }
+        k += run;
+    }
+    Ok(huge
+        .chunks(chunk_size / *PAGE_SIZE)
+        .map(|pages| pages.iter().filter(|&&h| h).count() as f64 / pages.len() as f64)
+        .collect())
+}
+
+/// The portion of the present pages accessed in each chunk of the given pages.
+fn accessed_ratios(accessed: &[Option<bool>], chunk_pages: usize) -> Vec<f64> {
+    accessed
+        .chunks(chunk_pages)
+        .map(|pages| {
+            let present = pages.iter().flatten().count();
+            let accessed = pages.iter().flatten().filter(|&&a| a).count();
+            accessed as f64 / present as f64
+        })
+        .collect()
+}
+
+/// Like `logical_ratios` but of the portion of the present pages accessed.
+fn logical_accessed(
+    start: usize,
+    logical: &LogicalPages,
+    accessed: &[Option<bool>],
+    page_size: usize,
+) -> String {
+    let ratios: Vec<_> = logical
+        .iter()
+        .map(|(name, ranges)| {
+            let pages = ranges.iter().flat_map(|r| r.clone().step_by(page_size));
+            let pages: Vec<_> = pages
+                .filter_map(|addr| *accessed.get((addr - start) / page_size)?)
+                .collect();
+            let n = pages.iter().filter(|&&a| a).count();
+            format!("{name} {:.4}", n as f64 / pages.len() as f64)
+        })
+        .collect();
+    ratios.join(", ")
+}
+
+/// Magic of the dumps of `--residency-dump`, followed by the page size, the number of pages and
+/// the time of the dump in ns since the Unix epoch, then a bitmap of the pages in DRAM and one of
+/// the present pages, all little-endian 64-bit words with bit `i` of word `w` for page `64w + i`.
+const RESIDENCY_MAGIC: &[u8; 8] = b"GUPSRES\0";
+/// Number of the next residency dump.
+static RESIDENCY_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+fn residency_dump(
+    ptes: &[pagemap::PageMapEntry],
+    page_size: usize,
+    dir: &path::Path,
+    label: &str,
+) -> Result<()> {
+    let pages = ptes.len() / (page_size / *PAGE_SIZE);
+    let (mut dram, mut present) = (
+        vec![0u64; pages.div_ceil(64)],
+        vec![0u64; pages.div_ceil(64)],
+    );
+    for (i, e) in ptes.iter().step_by(page_size / *PAGE_SIZE).enumerate() {
+        if e.present() {
+            present[i / 64] |= 1 << (i % 64);
+            if DRAM_PFN_RANGE.contains(&e.pfn()?) {
+                dram[i / 64] |= 1 << (i % 64);
+            }
+        }
+    }
+    let now = time::SystemTime::now().duration_since(time::UNIX_EPOCH)?;
+    let mut out = RESIDENCY_MAGIC.to_vec();
+    for word in [page_size as u64, pages as u64, now.as_nanos() as u64] {
+        out.extend(word.to_le_bytes());
+    }
+    out.extend(dram.iter().chain(&present).flat_map(|w| w.to_le_bytes()));
+    let seq = RESIDENCY_SEQ.fetch_add(1, Ordering::Relaxed);
+    let path = dir.join(format!("residency-{seq}-{}.bin", now.as_millis()));
+    std::fs::write(&path, out).map_err(|e| format!("{}: {e}", path.display()))?;
+    let count = |plane: &[u64]| plane.iter().map(|w| w.count_ones()).sum::<u32>();
+    tracing::info!(
+        "residency: iteration {label} {} in dram {} present of {pages} pages dumped to {}",
+        count(&dram),
+        count(&present),
+        path.display()
+    );
+    Ok(())
+}
+
+fn heatmap_render(render: &HeatmapRender) -> Result<()> {
+    let dump =
+        std::fs::read(&render.dump).map_err(|e| format!("{}: {e}", render.dump.display()))?;
+    let word = |k: usize| -> Option<u64> {
+        Some(u64::from_le_bytes(
+            dump.get(8 * k..8 * k + 8)?.try_into().unwrap(),
+        ))
+    };
+    let pages = match (dump.starts_with(RESIDENCY_MAGIC), word(2)) {
+        (true, Some(pages)) => pages as usize,
+        _ => return Err(format!("{} is no residency dump", render.dump.display()).into()),
+    };
+    let plane = pages.div_ceil(64);
+    if dump.len() != 8 * (4 + 2 * plane) {
+        return Err(format!("{} is truncated", render.dump.display()).into());
+    }
+    let bit = |plane_start: usize, i: usize| word(plane_start + i / 64).unwrap() >> (i % 64) & 1;
+    if render.scale == 0 || render.width == 0 {
+        return Err("scale and width must be positive".into());
+    }
+    // the portions in DRAM and present of the pages of each pixel
+    let pixels: Vec<(usize, f64, f64)> = (0..pages)
+        .step_by(render.scale)
+        .map(|first| {
+            let range = first..(first + render.scale).min(pages);
+            let n = range.len() as f64;
+            let dram: u64 = range.clone().map(|i| bit(4, i)).sum();
+            let present: u64 = range.map(|i| bit(4 + plane, i)).sum();
+            (first, dram as f64 / n, present as f64 / n)
+        })
+        .collect();
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    let res = match render.format.as_str() {
+        "csv" => pixels
+            .iter()
+            .try_for_each(|(first, dram, present)| writeln!(out, "{first},{dram},{present}")),
+        _ => {
+            let height = pixels.len().div_ceil(render.width);
+            let mut image = format!("P5\n{} {height}\n255\n", render.width).into_bytes();
+            // half grey for present, the other half for in DRAM
+            image.extend(
+                pixels
+                    .iter()
+                    .map(|(_, dram, present)| (127.5 * (dram + present)).round() as u8),
+            );
+            image.resize(image.len() + height * render.width - pixels.len(), 0);
+            out.write_all(&image)
+        }
     };
+    match res.and_then(|()| out.flush()) {
+        // e.g. piped into `head`
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        res => Ok(res?),
+    }
 }
 
-fn mem_region(addr: u64) -> pagemap::MemoryRegion {
-    let maps = pagemap::maps(process::id() as _).unwrap();
-    let map = maps
+/// The DRAM and present portions of the chunks of a region, as by `dram_ratio`.
+type Ratios = (Vec<f64>, Vec<f64>);
+
+/// The page-aligned address ranges of each named logical region of the workload.
+type LogicalPages = Vec<(String, Vec<ops::Range<usize>>)>;
+
+/// The pages of the logical regions of the hotset workload at `base`, its named regions or else
+/// its hot and cold regions as initially laid out in each part, a page shared by regions going to
+/// the one holding most of its bytes.
+fn logical_pages(args: &Args, base: usize, len: usize) -> Result<Option<LogicalPages>> {
+    let Workload::Hotset {
+        regions, reverse, ..
+    } = &args.workload
+    else {
+        return Ok(None);
+    };
+    let (part, g) = (args.region_len(), args.granularity.unit());
+    let top = part / g * g;
+    let layout: Vec<(String, Vec<ops::Range<usize>>)> = match hot_ranges(args)? {
+        Some(hots) => {
+            let colds = complement(&hots, top);
+            vec![("hot".into(), hots), ("cold".into(), colds)]
+        }
+        None => regions_layout(part, g, regions)?
+            .into_iter()
+            .map(|(name, r, _)| {
+                let r = match reverse {
+                    true => top - r.end..top - r.start,
+                    false => r,
+                };
+                (name, Vec::from([r]))
+            })
+            .collect(),
+    };
+    let parts = match args.partitioned {
+        true => len / part,
+        false => 1,
+    };
+    let mut segments = vec![];
+    for t in 0..parts {
+        for (k, (_, ranges)) in layout.iter().enumerate() {
+            let offset = base + t * part;
+            let ranges = ranges.iter().filter(|r| !r.is_empty());
+            segments.extend(ranges.map(|r| (offset + r.start..offset + r.end, k)));
+        }
+    }
+    let page = args.page_size();
+    let owner = |p: usize| {
+        let mut bytes = vec![0; layout.len()];
+        for (r, k) in &segments {
+            bytes[*k] += r
+                .end
+                .min((p + 1) * page)
+                .saturating_sub(r.start.max(p * page));
+        }
+        (0..layout.len()).max_by_key(|&k| (bytes[k], std::cmp::Reverse(k)))
+    };
+    let mut pages = vec![vec![]; layout.len()];
+    for (r, k) in &segments {
+        // only the first and last page may be shared
+        let (first, last) = (r.start / page, (r.end - 1) / page);
+        let start = first + (owner(first) != Some(*k)) as usize;
+        let end = last + 1 - (last >= start && owner(last) != Some(*k)) as usize;
+        if start < end {
+            pages[*k].push(start * page..end * page);
+        }
+    }
+    let logical = layout.into_iter().zip(pages).map(|((name, _), mut pages)| {
+        // merge the pages of ranges meeting on a page
+        pages.sort_by_key(|r: &ops::Range<usize>| r.start);
+        pages.dedup_by(|next, prev| match next.start <= prev.end {
+            true => {
+                prev.end = prev.end.max(next.end);
+                true
+            }
+            false => false,
+        });
+        (name, pages)
+    });
+    Ok(Some(logical.collect()))
+}
+
+/// The DRAM and present portions of each logical region, as by `dram_ratio`, formatted for a
+/// report.
+fn logical_ratios(portions: &[(String, f64, f64)]) -> String {
+    let ratios: Vec<_> = portions
         .iter()
-        .filter(|entry| entry.memory_region().contains(addr))
-        .next()
-        .unwrap();
-    map.memory_region()
+        .map(|(name, dram, present)| format!("{name} {dram:.4} present {present:.4}"))
+        .collect();
+    ratios.join(", ")
 }
 
-fn dram_ratio(region: pagemap::MemoryRegion, chunk_size: usize) -> Vec<f64> {
+/// The DRAM and present portions of each logical region by name.
+fn logical_portions(
+    region: pagemap::MemoryRegion,
+    logical: &LogicalPages,
+    page_size: usize,
+) -> Vec<(String, f64, f64)> {
     let ptes = pagemap::PageMap::new(process::id() as _)
         .unwrap()
         .pagemap_region(&region)
         .unwrap();
-    ptes.chunks(chunk_size / *PAGE_SIZE)
-        .map(|ptes| {
-            let dram = ptes
-                .iter()
-                .filter(|e| e.present() && DRAM_PFN_RANGE.contains(&e.pfn().unwrap()))
-                .count();
-            dram as f64 / ptes.len() as f64
+    let start = region.start_address() as usize;
+    logical
+        .iter()
+        .map(|(name, ranges)| {
+            let (mut dram, mut present, mut n) = (0, 0, 0);
+            let pages = ranges.iter().flat_map(|r| r.clone().step_by(page_size));
+            for e in pages.filter_map(|addr| ptes.get((addr - start) / *PAGE_SIZE)) {
+                dram += (e.present() && DRAM_PFN_RANGE.contains(&e.pfn().unwrap())) as usize;
+                (present, n) = (present + e.present() as usize, n + 1);
+            }
+            let (dram, present) = (dram as f64 / present as f64, present as f64 / n as f64);
+            (name.clone(), dram, present)
         })
         .collect()
 }
 
+/// The DRAM portion of the hot region among the logical ones, unless none of it is present.
+fn hot_portion(portions: &[(String, f64, f64)]) -> Option<f64> {
+    portions
+        .iter()
+        .find(|(name, ..)| name == "hot")
+        .map(|&(_, dram, _)| dram)
+        .filter(|dram| !dram.is_nan())
+}
+
 // The drgn script to get dram pfn range:
 // ```python
 // #!/usr/bin/env python3
@@ -400,6 +8860,28 @@ fn dram_ratio(region: pagemap::MemoryRegion, chunk_size: usize) -> Vec<f64> {
 lazy_static::lazy_static! {
     static ref PAGE_SIZE: usize = pagemap::page_size().unwrap() as _;
 
+    static ref MEMORY_BLOCK_SIZE: u64 = {
+        let size = std::fs::read_to_string("/sys/devices/system/memory/block_size_bytes").unwrap();
+        u64::from_str_radix(size.trim(), 16).unwrap()
+    };
+
+    /// The NUMA node of each memory block, as linked from the node directories.
+    static ref MEMORY_BLOCK_NODES: std::collections::BTreeMap<u64, i32> = {
+        let mut blocks = std::collections::BTreeMap::new();
+        for node in std::fs::read_dir("/sys/devices/system/node").unwrap() {
+            let node = node.unwrap();
+            let name = node.file_name().into_string().unwrap();
+            let Some(Ok(id)) = name.strip_prefix("node").map(str::parse::<i32>) else { continue };
+            for block in std::fs::read_dir(node.path()).unwrap() {
+                let name = block.unwrap().file_name().into_string().unwrap();
+                if let Some(Ok(block)) = name.strip_prefix("memory").map(str::parse::<u64>) {
+                    blocks.insert(block, id);
+                }
+            }
+        }
+        blocks
+    };
+
     static ref DRAM_PFN_RANGE : ops::Range<u64> = {
         let output = process::Command::new("sudo").arg("-E").arg("dram-pfn.py").env("LD_PRELOAD", "")
             .output()
@@ -412,3 +8894,294 @@ lazy_static::lazy_static! {
         start..end
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrambled_is_a_bijection() {
+        for len in [1, 3, 7, 17, 1000, 4097, 65535] {
+            let scrambled = Scrambled::new(Uniform::new(0, len), len, 42);
+            let mut seen = vec![false; len];
+            for x in 0..len {
+                let y = scrambled.permute(x);
+                assert!(y < len, "{x} maps to {y} outside of {len}");
+                assert!(!seen[y], "{y} is hit twice for {len}");
+                seen[y] = true;
+            }
+        }
+    }
+
+    #[test]
+    fn results_round_trip() {
+        let args = Args::from_iter([
+            "gups", "-t", "2", "-u", "1000", "-l", "1M", "-g", "8", "random",
+        ]);
+        let results = Results {
+            schema: Results::SCHEMA,
+            args: serde_json::to_value(&args).unwrap(),
+            env: Env {
+                hostname: "host".into(),
+                kernel: "6.8.0".into(),
+                page_size: 4096,
+                dram_pfn_range: Some(0..1 << 20),
+            },
+            iterations: vec![IterationResult {
+                label: "last".into(),
+                gups: 0.125,
+                elapsed: 2.5,
+                hop_ns: Some(95.5),
+                samples: vec![GupsSample {
+                    time: 1.0,
+                    hitherto: 0.1,
+                    instantaneous: 0.2,
+                    faults: Faults {
+                        minor_faults: Some(3),
+                        swap_kb: Some(0),
+                        ..Default::default()
+                    },
+                }],
+                dram_ratios: vec![RatioSample {
+                    updates: vec![7, 0],
+                    thp: vec![0.5, 1.0],
+                    tiers: [("fast".to_owned(), vec![0.25, 0.0])].into(),
+                    ..RatioSample::new(time::Duration::from_secs(1), &[0.75, f64::NAN], &[1.0, 0.0])
+                }],
+            }],
+        };
+        let json = serde_json::to_string(&results).unwrap();
+        let back: Results = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.iterations[0].dram_ratios[0].dram, [Some(0.75), None]);
+        assert_eq!(back, results);
+    }
+
+    /// The mean and standard deviation of the given samples.
+    fn moments(samples: impl Iterator<Item = usize>) -> (f64, f64) {
+        let (mut n, mut sum, mut squares) = (0.0, 0.0, 0.0);
+        for x in samples {
+            (n, sum, squares) = (n + 1.0, sum + x as f64, squares + (x as f64).powi(2));
+        }
+        let mean = sum / n;
+        (mean, (squares / n - mean * mean).sqrt())
+    }
+
+    #[test]
+    fn truncated_normal_moments() {
+        let len = 1_000_000;
+        let mut rng: rand::rngs::StdRng = rand::SeedableRng::seed_from_u64(7);
+        let sigma = 0.1 * len as f64;
+        // centered, the truncation 5 sigma away is negligible
+        let d = Truncated::new(Normal::new(0.5 * len as f64, sigma).unwrap(), len);
+        let (mean, stddev) = moments((0..1_000_000).map(|_| d.sample(&mut rng)));
+        assert!(
+            (mean - 0.5 * len as f64).abs() < 0.01 * sigma,
+            "mean {mean}"
+        );
+        assert!((stddev - sigma).abs() < 0.01 * sigma, "stddev {stddev}");
+        // centered on the first element, the half-normal rather than a pile-up on it
+        let d = Truncated::new(Normal::new(0.0, sigma).unwrap(), len);
+        let (mean, stddev) = moments((0..1_000_000).map(|_| d.sample(&mut rng)));
+        let pi = std::f64::consts::PI;
+        let (expected_mean, expected_stddev) =
+            (sigma * (2.0 / pi).sqrt(), sigma * (1.0 - 2.0 / pi).sqrt());
+        assert!((mean - expected_mean).abs() < 0.01 * sigma, "mean {mean}");
+        assert!(
+            (stddev - expected_stddev).abs() < 0.01 * sigma,
+            "stddev {stddev}"
+        );
+    }
+
+    #[test]
+    fn pareto_top_share() {
+        let (len, shape) = (10_000, 0.5);
+        let mut rng: rand::rngs::StdRng = rand::SeedableRng::seed_from_u64(7);
+        let pareto = Pareto::new(1.0, shape).unwrap().map(|x| x - 1.0);
+        let d = Truncated::new(pareto, len);
+        let n = 1_000_000;
+        let top = (0..n).filter(|_| d.sample(&mut rng) < len / 100).count();
+        // the cdf 1 - (1 + x)^-shape conditioned on the memory region
+        let cdf = |x: f64| 1.0 - (1.0 + x).powf(-shape);
+        let expected = cdf((len / 100) as f64) / cdf(len as f64);
+        let share = top as f64 / n as f64;
+        assert!(
+            (share - expected).abs() < 0.002,
+            "share {share} of {expected}"
+        );
+    }
+
+    #[test]
+    fn paged_offsets_uniform() {
+        let (npages, per_page) = (64, 512);
+        let mut rng: rand::rngs::StdRng = rand::SeedableRng::seed_from_u64(7);
+        let pages = ZipfDistribution::new(npages, 1.0)
+            .unwrap()
+            .map(|rank| rank - 1);
+        let d = Paged::new(pages, per_page);
+        // the offsets within the hottest page
+        let mut counts = vec![0usize; per_page];
+        let mut n = 0;
+        for x in (0..2_000_000).map(|_| d.sample(&mut rng)) {
+            assert!(x < npages * per_page);
+            if x / per_page == 0 {
+                (counts[x % per_page], n) = (counts[x % per_page] + 1, n + 1);
+            }
+        }
+        let expected = n as f64 / per_page as f64;
+        let chi2: f64 = counts
+            .iter()
+            .map(|&c| (c as f64 - expected).powi(2) / expected)
+            .sum();
+        // the 99.9th percentile of chi-square of 511 degrees of freedom is about 617
+        assert!(chi2 < 617.0, "chi-square {chi2} over {n} draws");
+    }
+
+    #[test]
+    fn update_ops() {
+        let i = 2;
+        for g in [1, 2, 4, 8, 16] {
+            // the element as its little-endian bytes
+            let bytes = |value: u128| value.to_le_bytes()[..g].to_vec();
+            for (op, expected) in [
+                (Op::Add, bytes(1)),
+                (Op::Xor, bytes(i as u128)),
+                (Op::Store, bytes(STORE_VALUE as u128)),
+            ] {
+                // u128 for the alignment of the largest element
+                let mut buf = [0u128; 8];
+                let mem = unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, 128) };
+                update(mem, g, i, op);
+                let mut want = [0; 128];
+                want[i * g..(i + 1) * g].copy_from_slice(&expected);
+                assert_eq!(mem, &want[..], "{op:?} of {g} bytes");
+                // again on top of the first
+                update(mem, g, i, op);
+                let again = match op {
+                    Op::Add => bytes(2),
+                    Op::Xor => bytes(0),
+                    _ => expected,
+                };
+                want[i * g..(i + 1) * g].copy_from_slice(&again);
+                assert_eq!(mem, &want[..], "twice {op:?} of {g} bytes");
+            }
+            let mut buf = [0x0101_0101_0101_0101_0101_0101_0101_0101u128; 8];
+            let mem = unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, 128) };
+            // the checksum is of the low 8 bytes
+            let expected = u64::from_le_bytes([1; 8]) >> (64 - 8 * g.min(8));
+            assert_eq!(load(mem, g, i), expected, "load of {g} bytes");
+            assert!(mem.iter().all(|&b| b == 1), "load of {g} bytes wrote");
+        }
+    }
+
+    #[test]
+    fn bytes_per_update() {
+        let args = |extra: &[&str]| {
+            let base = ["gups", "-t", "1", "-u", "1000", "-l", "1M"];
+            Args::from_iter(base.iter().chain(extra).chain(&["random"]))
+        };
+        for g in [1, 2, 4, 8, 16] {
+            for read in [0, 30, 100] {
+                let (gs, rs) = (g.to_string(), read.to_string());
+                let (p, g) = (read as f64 / 100.0, g as f64);
+                for (op, expected) in [
+                    ("add", (g, (1.0 - p) * g)),
+                    ("xor", (g, (1.0 - p) * g)),
+                    ("store", (p * g, (1.0 - p) * g)),
+                    ("load", (g, 0.0)),
+                    // the copy size rather than the element size
+                    ("copy", (p * 256.0, (1.0 - p) * 256.0)),
+                ] {
+                    let a = args(&[
+                        "-g",
+                        &gs,
+                        "--op",
+                        op,
+                        "--read-ratio",
+                        &rs,
+                        "--copy-size",
+                        "256",
+                    ]);
+                    let (r, w) = a.bytes_per_update().unwrap();
+                    assert!(
+                        (r - expected.0).abs() < 1e-9 && (w - expected.1).abs() < 1e-9,
+                        "{op} of {g} bytes read {read}%: {r} {w} rather than {expected:?}"
+                    );
+                }
+            }
+        }
+        // weighted by the size classes
+        let (r, w) = args(&["-g", "8:3,64:1"]).bytes_per_update().unwrap();
+        assert_eq!((r, w), (22.0, 22.0));
+        let chase = Args::from_iter([
+            "gups", "-t", "1", "-u", "1", "-l", "1M", "-g", "16", "chase",
+        ]);
+        assert_eq!(chase.bytes_per_update(), None);
+    }
+
+    #[test]
+    fn unit_rate_and_show() {
+        // decimal rather than 2^30
+        assert_eq!(Unit::Gups.rate(3_000_000_000, 2.0), 1.5);
+        assert_eq!(Unit::Mups.rate(3_000_000, 2.0), 1.5);
+        assert_eq!(Unit::Ops.rate(3, 2.0), 1.5);
+        // down to the thousands of updates per second
+        assert_eq!(Unit::Gups.show(1.23456789), "1.234568");
+        assert_eq!(Unit::Mups.show(1.23456789), "1.235");
+        assert_eq!(Unit::Ops.show(1.5), "2");
+        let updates = 1 << 30;
+        assert_eq!(Unit::Gups.show(Unit::Gups.rate(updates, 1.0)), "1.073742");
+    }
+
+    #[test]
+    fn random_within_range() {
+        let end = 1000;
+        let mut rng: rand::rngs::StdRng = rand::SeedableRng::seed_from_u64(7);
+        for hole in [0..0, 100..300, 900..1000] {
+            let nelems = end - hole.len();
+            let d = Uniform::new(0, nelems);
+            let forward = Holed::new(d, hole.clone());
+            let backward = Holed::new(Backwards::new(d, nelems - 1), hole.clone());
+            let (mut lo, mut hi) = (usize::MAX, 0);
+            for x in
+                (0..100_000).flat_map(|_| [forward.sample(&mut rng), backward.sample(&mut rng)])
+            {
+                assert!(
+                    x < end && !hole.contains(&x),
+                    "{x} drawn with hole {hole:?}"
+                );
+                (lo, hi) = (lo.min(x), hi.max(x));
+            }
+            // both ends of what is left are reached
+            let last = match hole.end {
+                1000 => hole.start - 1,
+                _ => end - 1,
+            };
+            assert_eq!((lo, hi), (0, last), "hole {hole:?}");
+        }
+    }
+
+    #[test]
+    fn self_similar_top_share() {
+        let (len, bias) = (1000, 0.8);
+        let mut rng: rand::rngs::StdRng = rand::SeedableRng::seed_from_u64(7);
+        let d = SelfSimilar::new(len, bias);
+        let top = len / 5;
+        // the mass below `top` along the halvings, `bias` going to the lower half of each
+        let (mut expected, mut p, (mut lo, mut hi)) = (0.0, 1.0, (0, len));
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if top <= mid {
+                (p, hi) = (p * bias, mid);
+            } else {
+                (expected, p, lo) = (expected + p * bias, p * (1.0 - bias), mid);
+            }
+        }
+        expected += p * (lo < top) as u8 as f64;
+        let n = 1_000_000;
+        let share = (0..n).filter(|_| d.sample(&mut rng) < top).count() as f64 / n as f64;
+        assert!(
+            (share - expected).abs() < 0.002,
+            "share {share} of {expected}"
+        );
+    }
+}